@@ -2,3 +2,4 @@ pub mod args;
 pub mod euclid;
 pub mod io;
 pub mod matrix;
+pub mod profile;
@@ -1,25 +1,93 @@
-use clap::{ArgGroup, Parser};
+use clap::error::ErrorKind as ClapErrorKind;
+use clap::{ArgGroup, Args, CommandFactory, Parser};
+use std::ffi::OsString;
+use crate::aoc::io::open_input;
+use std::io::{BufRead, ErrorKind};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 
 #[derive(Debug, Parser)]
 #[command(group(ArgGroup::new("puzzle")))]
 #[command(author, version, long_about = None)]
-struct RawArguments {
+struct RawArguments<E: Args> {
     #[arg(short = '1', group = "puzzle")]
     p1: bool,
 
     #[arg(short = '2', group = "puzzle")]
     p2: bool,
 
+    /// Solve both puzzles, printing the first answer first
+    #[arg(short = 'b', long, group = "puzzle")]
+    both: bool,
+
+    /// Require one of ‹-1›, ‹-2› or ‹--both› instead of defaulting to ‹-1›
+    #[arg(long)]
+    strict: bool,
+
+    /// Print how long the timed parts of the solution took to stderr
+    #[arg(long)]
+    time: bool,
+
+    /// Report allocation statistics to stderr after solving
+    #[cfg(feature = "profile")]
+    #[arg(long)]
+    profile: bool,
+
+    /// Input file, or ‹-› to read standard input
+    #[arg(default_value = "-")]
     file_name: String,
+
+    #[command(flatten)]
+    extra: E,
 }
 
+/* Placeholder for days that do not take any additional options. */
+#[derive(Debug, Args)]
+pub struct NoArguments {}
+
 #[derive(Debug)]
 pub struct Arguments {
     pub puzzle: Puzzle,
+    pub both: bool,
+    pub time: bool,
     pub file_name: String,
+
+    // Only there to be dropped at the end of ‹main›.
+    #[cfg(feature = "profile")]
+    _profile: Option<ProfileReport>,
+}
+
+/* Reports allocation statistics when dropped, i.e. after ‹main› is done
+ * with its ‹Arguments›, so that no day has to do so by itself. */
+#[cfg(feature = "profile")]
+#[derive(Debug)]
+struct ProfileReport;
+
+#[cfg(feature = "profile")]
+impl Drop for ProfileReport {
+    fn drop(&mut self) {
+        crate::aoc::profile::report();
+    }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/* Set from ‹--time› once the arguments are parsed, so that ‹timed› can be
+ * used anywhere without passing ‹Arguments› around. */
+static TIMING: AtomicBool = AtomicBool::new(false);
+
+/* Runs ‹f›, reporting its duration under ‹label› if ‹--time› was given. */
+pub fn timed<T, F: FnOnce() -> T>(label: &str, f: F) -> T {
+    if !TIMING.load(Ordering::Relaxed) {
+        return f();
+    }
+
+    let start = Instant::now();
+    let result = f();
+    eprintln!("{}: {:?}", label, start.elapsed());
+
+    result
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Puzzle {
     P1,
     P2,
@@ -27,14 +95,156 @@ pub enum Puzzle {
 
 impl Arguments {
     pub fn parse() -> Arguments {
-        let raw = RawArguments::parse();
+        Self::parse_with::<NoArguments>().0
+    }
+
+    /* Parses common arguments along with day-specific options ‹E›; exits
+     * with a usage message on invalid arguments. */
+    pub fn parse_with<E: Args>() -> (Arguments, E) {
+        Self::try_parse_with::<E>().unwrap_or_else(|err| err.exit())
+    }
+
+    pub fn try_parse() -> Result<Arguments, clap::Error> {
+        Ok(Self::try_parse_with::<NoArguments>()?.0)
+    }
+
+    pub fn try_parse_with<E: Args>() -> Result<(Arguments, E), clap::Error> {
+        let (args, extra) = Self::try_parse_from::<E, _, _>(std::env::args_os())?;
+        TIMING.store(args.time, Ordering::Relaxed);
+
+        Ok((args, extra))
+    }
+
+    fn try_parse_from<E, I, T>(argv: I) -> Result<(Arguments, E), clap::Error>
+            where E: Args, I: IntoIterator<Item = T>, T: Into<OsString> + Clone {
+        let argv = argv.into_iter().map(Into::into).collect::<Vec<OsString>>();
+        let raw = RawArguments::<E>::try_parse_from(&argv)?;
+
+        if raw.strict && !(raw.p1 || raw.p2 || raw.both) {
+            let mut command = RawArguments::<E>::command();
+            if let Some(name) = argv.first() {
+                command = command.bin_name(name.to_string_lossy().into_owned());
+            }
+
+            return Err(command.error(
+                ClapErrorKind::MissingRequiredArgument,
+                "‹--strict› requires one of ‹-1›, ‹-2› or ‹--both›",
+            ));
+        }
+
+        Ok(Self::from_raw(raw))
+    }
 
-        // If ‹p2› is false, we always default to ‹p1›.
+    fn from_raw<E: Args>(raw: RawArguments<E>) -> (Arguments, E) {
+        // If ‹p2› is false, we always default to ‹p1›; with ‹both›, days
+        // go through ‹puzzles› instead.
         let puzzle = if raw.p2 { Puzzle::P2 } else { Puzzle::P1 };
 
-        Arguments {
+        let args = Arguments {
             puzzle,
+            both: raw.both,
+            time: raw.time,
             file_name: raw.file_name,
+            // Not ‹then_some›: a guard built and dropped right away would
+            // report even without ‹--profile›.
+            #[cfg(feature = "profile")]
+            _profile: raw.profile.then(|| ProfileReport),
+        };
+
+        (args, raw.extra)
+    }
+
+    /* Opens the input, ‹-› being standard input, with an error message fit
+     * for the user. */
+    pub fn open(&self) -> Result<Box<dyn BufRead>, String> {
+        open_input(&self.file_name).map_err(|err| {
+            let reason = match err.kind() {
+                ErrorKind::NotFound => String::from("No such file or directory"),
+                ErrorKind::PermissionDenied => String::from("Permission denied"),
+                kind => kind.to_string(),
+            };
+
+            format!("Cannot open '{}': {}", self.file_name, reason)
+        })
+    }
+
+    /* Selected puzzles in order; every day solves each of them in turn. */
+    pub fn puzzles(&self) -> Vec<Puzzle> {
+        if self.both {
+            vec![Puzzle::P1, Puzzle::P2]
+        } else {
+            vec![self.puzzle]
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_from(args: &[&str]) -> Result<Arguments, clap::Error> {
+        Ok(Arguments::try_parse_from::<NoArguments, _, _>(args)?.0)
+    }
+
+    #[test]
+    fn selection() {
+        assert_eq!(parse_from(&["day", "input"]).unwrap().puzzles(), vec![Puzzle::P1]);
+        assert_eq!(parse_from(&["day", "-2", "input"]).unwrap().puzzles(), vec![Puzzle::P2]);
+        assert_eq!(parse_from(&["day", "-b", "input"]).unwrap().puzzles(),
+                   vec![Puzzle::P1, Puzzle::P2]);
+        assert_eq!(parse_from(&["day", "--both"]).unwrap().file_name, "-");
+
+        assert!(parse_from(&["day", "-1", "-b", "input"]).is_err());
+    }
+
+    #[test]
+    fn strict_needs_puzzle() {
+        let err = parse_from(&["day", "--strict", "input"]).expect_err("No puzzle given");
+        assert_eq!(err.kind(), ClapErrorKind::MissingRequiredArgument);
+
+        assert_eq!(parse_from(&["day", "--strict", "-2", "input"]).unwrap().puzzle, Puzzle::P2);
+        assert_eq!(parse_from(&["day", "input"]).unwrap().puzzle, Puzzle::P1);
+    }
+
+    #[test]
+    fn conflicting_puzzles() {
+        let err = parse_from(&["day", "-1", "-2", "input"]).expect_err("Two puzzles given");
+        assert_eq!(err.kind(), ClapErrorKind::ArgumentConflict);
+
+        assert!(parse_from(&["day", "--strict", "-1", "-2", "input"]).is_err());
+    }
+
+    #[test]
+    fn open_missing() {
+        let args = parse_from(&["day", "/nonexistent/foo"]).unwrap();
+        let err = args.open().err().expect("File does not exist");
+
+        assert_eq!(err, "Cannot open '/nonexistent/foo': No such file or directory");
+    }
+
+    #[test]
+    fn time_flag() {
+        assert!(parse_from(&["day", "--time", "input"]).unwrap().time);
+        assert!(!parse_from(&["day", "input"]).unwrap().time);
+    }
+
+    #[cfg(feature = "profile")]
+    #[test]
+    fn profile_flag() {
+        assert!(parse_from(&["day", "--profile", "input"]).unwrap()._profile.is_some());
+        assert!(parse_from(&["day", "input"]).unwrap()._profile.is_none());
+    }
+
+    #[cfg(not(feature = "profile"))]
+    #[test]
+    fn profile_unavailable() {
+        let err = parse_from(&["day", "--profile", "input"]).expect_err("Not compiled in");
+        assert_eq!(err.kind(), ClapErrorKind::UnknownArgument);
+    }
+
+    #[test]
+    fn timed_result() {
+        assert_eq!(timed("answer", || 6 * 7), 42);
+        assert_eq!(timed("text", || String::from("ok")), "ok");
+    }
+}
@@ -1,4 +1,11 @@
+use num::{PrimInt, Signed};
+use std::fmt::Display;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::str::FromStr;
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Direction {
     North,
     West,
@@ -6,6 +13,26 @@ pub enum Direction {
     East,
 }
 
+impl Direction {
+    pub fn rotate_cw(&self) -> Self {
+        match self {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+        }
+    }
+
+    pub fn rotate_ccw(&self) -> Self {
+        match self {
+            Direction::North => Direction::West,
+            Direction::West => Direction::South,
+            Direction::South => Direction::East,
+            Direction::East => Direction::North,
+        }
+    }
+}
+
 pub struct DirectionIterator {
     view: &'static [Direction],
 }
@@ -18,6 +45,14 @@ impl DirectionIterator {
         Direction::East,
     ];
 
+    /* Unit vectors of ‹ORDER›, matching ‹Vector::from(&Direction)›. */
+    pub const DELTAS: [Vector; 4] = [
+        Vector::new(0, 1),
+        Vector::new(-1, 0),
+        Vector::new(0, -1),
+        Vector::new(1, 0),
+    ];
+
     pub fn new() -> Self {
         Self { view: &Self::ORDER }
     }
@@ -41,56 +76,270 @@ impl Iterator for DirectionIterator {
         self.view = &self.view[1..];
         rv
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.view.len(), Some(self.view.len()))
+    }
+}
+
+impl DoubleEndedIterator for DirectionIterator {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (last, rest) = self.view.split_last()?;
+        self.view = rest;
+        Some(*last)
+    }
 }
 
+impl ExactSizeIterator for DirectionIterator {}
+
+/* Cardinal and diagonal directions, listed counter-clockwise from ‹North›
+ * in the same fashion as ‹DirectionIterator›. */
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Direction8 {
+    North,
+    NorthWest,
+    West,
+    SouthWest,
+    South,
+    SouthEast,
+    East,
+    NorthEast,
+}
+
+impl Direction8 {
+    const ORDER: [Direction8; 8] = [
+        Direction8::North,
+        Direction8::NorthWest,
+        Direction8::West,
+        Direction8::SouthWest,
+        Direction8::South,
+        Direction8::SouthEast,
+        Direction8::East,
+        Direction8::NorthEast,
+    ];
+
+    const DIAGONALS: [Direction8; 4] = [
+        Direction8::NorthWest,
+        Direction8::SouthWest,
+        Direction8::SouthEast,
+        Direction8::NorthEast,
+    ];
+}
+
+impl From<&Direction> for Direction8 {
+    fn from(d: &Direction) -> Self {
+        match d {
+            Direction::North => Self::North,
+            Direction::West => Self::West,
+            Direction::South => Self::South,
+            Direction::East => Self::East,
+        }
+    }
+}
+
+impl From<Direction> for Direction8 {
+    fn from(d: Direction) -> Self {
+        Direction8::from(&d)
+    }
+}
+
+pub struct DiagonalIterator {
+    view: &'static [Direction8],
+}
+
+impl DiagonalIterator {
+    pub fn new() -> Self {
+        Self { view: &Direction8::DIAGONALS }
+    }
+}
+
+impl Default for DiagonalIterator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Iterator for DiagonalIterator {
+    type Item = Direction8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (first, rest) = self.view.split_first()?;
+        self.view = rest;
+        Some(*first)
+    }
+}
+
+/* Coordinates are generic over the integer type, ‹isize› being the default
+ * used by all grids. Operations that need a sign (rotations, directions)
+ * are only available for signed types. */
 #[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
-pub struct Point {
-    pub x: isize,
-    pub y: isize,
+pub struct Point<T = isize> {
+    pub x: T,
+    pub y: T,
 }
 
-pub type Vector = Point;
+pub type Vector<T = isize> = Point<T>;
 
-impl Point where {
-    pub fn new(x: isize, y: isize) -> Self {
+impl<T> Point<T> {
+    pub const fn new(x: T, y: T) -> Self {
         Self { x, y }
     }
+}
 
-    pub fn shift(&self, v: &Vector) -> Self {
-        Self::new(
-            self.x + v.x,
-            self.y + v.y,
-        )
+impl<T: PrimInt> Point<T> {
+    fn _abs_diff(a: T, b: T) -> usize {
+        (if a > b { a - b } else { b - a }).to_usize().expect("Distance overflow")
+    }
+
+    pub fn shift(&self, v: &Vector<T>) -> Self {
+        *self + *v
     }
 
     pub fn distance_from(&self, other: &Self) -> f64 {
-        (((self.x - other.x).pow(2) + (self.y - other.y).pow(2)) as f64).sqrt()
+        let dx = Self::_abs_diff(self.x, other.x) as f64;
+        let dy = Self::_abs_diff(self.y, other.y) as f64;
+
+        (dx * dx + dy * dy).sqrt()
     }
 
-    pub fn direction(&self, other: &Self) -> Vector {
-        use num::signum;
+    pub fn scale(&self, k: T) -> Self {
+        *self * k
+    }
 
+    pub fn manhattan_distance(&self, other: &Self) -> usize {
+        Self::_abs_diff(self.x, other.x) + Self::_abs_diff(self.y, other.y)
+    }
+
+    pub fn chebyshev_distance(&self, other: &Self) -> usize {
+        std::cmp::max(Self::_abs_diff(self.x, other.x), Self::_abs_diff(self.y, other.y))
+    }
+}
+
+impl<T: PrimInt + Signed> Point<T> {
+    /* Rotations around the origin; with ‹North› being ⟦+y⟧, these agree
+     * with ‹Direction::rotate_cw› and ‹Direction::rotate_ccw›. */
+    pub fn rotate_cw(&self) -> Self {
+        Self::new(self.y, -self.x)
+    }
+
+    pub fn rotate_ccw(&self) -> Self {
+        Self::new(-self.y, self.x)
+    }
+
+    pub fn rotate_180(&self) -> Self {
+        Self::new(-self.x, -self.y)
+    }
+
+    /* Every point from ‹self› to ‹other› inclusive; the segment must be
+     * horizontal, vertical or diagonal. */
+    pub fn line_to(&self, other: &Self) -> impl Iterator<Item = Self> {
+        let delta = *other - *self;
+        assert!(delta.x.is_zero() || delta.y.is_zero() || delta.x.abs() == delta.y.abs(),
+                "Segment is neither axis-aligned nor diagonal");
+
+        let start = *self;
+        let step = self.direction(other);
+        (0 ..= self.chebyshev_distance(other))
+            .map(move |i| start + step * T::from(i).expect("Coordinate overflow"))
+    }
+
+    pub fn direction(&self, other: &Self) -> Vector<T> {
         Vector::new(
-            signum(other.x - self.x),
-            signum(other.y - self.y),
+            (other.x - self.x).signum(),
+            (other.y - self.y).signum(),
         )
     }
 }
 
-impl From<&(isize, isize)> for Point {
-    fn from(p: &(isize, isize)) -> Self {
+impl Point {
+    pub fn neighbors8(&self) -> Neighbors8 {
+        Neighbors8 { center: *self, view: &Direction8::ORDER }
+    }
+
+    /* Nearest point of ‹bb›; points inside are returned unchanged. */
+    pub fn clamp_to(&self, bb: &BoundingBox) -> Point {
+        Point::new(self.x.clamp(bb.min.x, bb.max.x), self.y.clamp(bb.min.y, bb.max.y))
+    }
+}
+
+impl<T: Add<Output = T>> Add for Point<T> {
+    type Output = Point<T>;
+
+    fn add(self, other: Self) -> Self::Output {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl<T: Sub<Output = T>> Sub for Point<T> {
+    type Output = Point<T>;
+
+    fn sub(self, other: Self) -> Self::Output {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl<T: Mul<Output = T> + Copy> Mul<T> for Point<T> {
+    type Output = Point<T>;
+
+    fn mul(self, k: T) -> Self::Output {
+        Point::new(self.x * k, self.y * k)
+    }
+}
+
+impl<T: Neg<Output = T>> Neg for Point<T> {
+    type Output = Point<T>;
+
+    fn neg(self) -> Self::Output {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+impl<T: Copy> From<&(T, T)> for Point<T> {
+    fn from(p: &(T, T)) -> Self {
         Self::new(p.0, p.1)
     }
 }
 
-impl From<(isize, isize)> for Point {
-    fn from(p: (isize, isize)) -> Self {
+impl<T: Copy> From<(T, T)> for Point<T> {
+    fn from(p: (T, T)) -> Self {
         Point::from(&p)
     }
 }
 
-impl PartialEq<(isize, isize)> for Point {
-    fn eq(&self, other: &(isize, isize)) -> bool {
+/* Parses ‹x,y›, surrounding whitespace and signs are accepted. */
+impl<T> FromStr for Point<T>
+        where T: FromStr, T::Err: Display {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (x, y) = s.split_once(',')
+            .ok_or_else(|| format!("Expected ‹x,y›, got '{}'", s))?;
+
+        let coordinate = |c: &str| c.trim().parse::<T>()
+            .map_err(|e| format!("Invalid coordinate '{}' in '{}': {}", c, s, e));
+
+        Ok(Point::new(coordinate(x)?, coordinate(y)?))
+    }
+}
+
+/* Points are serialized as ‹[x, y]›. */
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for Point<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (&self.x, &self.y).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for Point<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (x, y) = <(T, T)>::deserialize(deserializer)?;
+        Ok(Point::new(x, y))
+    }
+}
+
+impl<T: PartialEq> PartialEq<(T, T)> for Point<T> {
+    fn eq(&self, other: &(T, T)) -> bool {
         self.x == other.0 && self.y == other.1
     }
 }
@@ -112,19 +361,156 @@ impl From<Direction> for Vector {
     }
 }
 
+impl From<&Direction8> for Vector {
+    fn from(d: &Direction8) -> Self {
+        match d {
+            Direction8::North => Self::new(0, 1),
+            Direction8::NorthWest => Self::new(-1, 1),
+            Direction8::West => Self::new(-1, 0),
+            Direction8::SouthWest => Self::new(-1, -1),
+            Direction8::South => Self::new(0, -1),
+            Direction8::SouthEast => Self::new(1, -1),
+            Direction8::East => Self::new(1, 0),
+            Direction8::NorthEast => Self::new(1, 1),
+        }
+    }
+}
+
+impl From<Direction8> for Vector {
+    fn from(d: Direction8) -> Vector {
+        Vector::from(&d)
+    }
+}
+
+/* Yields all eight surrounding points in ‹Direction8› order. No bounds
+ * are checked, callers are expected to filter e.g. with ‹Matrix::contains›. */
+pub struct Neighbors8 {
+    center: Point,
+    view: &'static [Direction8],
+}
+
+impl Iterator for Neighbors8 {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (first, rest) = self.view.split_first()?;
+        self.view = rest;
+        Some(self.center.shift(&Vector::from(first)))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct BoundingBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl BoundingBox {
+    pub fn new(min: Point, max: Point) -> Self {
+        assert!(min.x <= max.x && min.y <= max.y);
+        Self { min, max }
+    }
+
+    pub fn from_points<I>(points: I) -> Option<Self>
+            where I: IntoIterator<Item = Point> {
+        use std::cmp::{max, min};
+
+        points.into_iter()
+            .map(|p| Self::new(p, p))
+            .reduce(|b, p| Self::new(
+                Point::new(min(b.min.x, p.min.x), min(b.min.y, p.min.y)),
+                Point::new(max(b.max.x, p.max.x), max(b.max.y, p.max.y)),
+            ))
+    }
+
+    pub fn contains(&self, p: &Point) -> bool {
+        self.min.x <= p.x && p.x <= self.max.x
+            && self.min.y <= p.y && p.y <= self.max.y
+    }
+
+    pub fn width(&self) -> usize {
+        (self.max.x - self.min.x + 1) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max.y - self.min.y + 1) as usize
+    }
+}
+
+/* State of an outward clockwise spiral (as seen with rows going down),
+ * legs have lengths 1, 1, 2, 2, 3, 3, … */
+#[derive(Debug)]
+struct Spiral {
+    position: Point,
+    leg: usize,
+    step: usize,
+    turns: usize,
+}
+
+impl Spiral {
+    const HEADINGS: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+    fn advance(&mut self) -> Point {
+        let current = self.position;
+
+        self.position = self.position.shift(&Vector::from(Self::HEADINGS[self.turns % 4]));
+        self.step += 1;
+
+        if self.step == self.leg {
+            self.step = 0;
+            self.turns += 1;
+
+            if self.turns.is_multiple_of(2) {
+                self.leg += 1;
+            }
+        }
+
+        current
+    }
+}
+
+#[derive(Debug)]
+enum Order {
+    Linear,
+    Spiral(Spiral),
+}
+
 #[derive(Debug)]
 pub struct CoordGenerator {
     pub dir: Direction,
     cursor: isize,
+    step: isize,
     limit: [isize; 2],
+    order: Order,
 }
 
 impl CoordGenerator {
     pub fn new(dir: &Direction, width: usize, height: usize) -> CoordGenerator {
+        Self::with_step(dir, width, height, 1)
+    }
+
+    /* Like ‹new›, but yields only every ‹step›-th point of the walk. */
+    pub fn with_step(dir: &Direction, width: usize, height: usize, step: usize) -> CoordGenerator {
+        assert!(step > 0, "Step must be positive");
+
         Self {
             dir: *dir,
             cursor: 0,
+            step: step as isize,
             limit: [width as isize, height as isize],
+            order: Order::Linear,
+        }
+    }
+
+    /* Walks the rectangle in rings around ‹center›, skipping the parts of
+     * rings that lie outside of it. */
+    pub fn spiral(center: Point, width: usize, height: usize) -> CoordGenerator {
+        Self {
+            dir: Direction::East,
+            cursor: 0,
+            step: 1,
+            limit: [width as isize, height as isize],
+            order: Order::Spiral(Spiral { position: center, leg: 1, step: 0, turns: 0 }),
         }
     }
 
@@ -149,9 +535,21 @@ impl Iterator for CoordGenerator {
             return None;
         }
 
+        if let Order::Spiral(spiral) = &mut self.order {
+            let [width, height] = self.limit;
+            let mut p = spiral.advance();
+
+            while !(0 <= p.x && p.x < width && 0 <= p.y && p.y < height) {
+                p = spiral.advance();
+            }
+
+            self.cursor += 1;
+            return Some(p);
+        }
+
         let cursor = self.cursor;
         let rev_cursor = self._lim() - cursor - 1;
-        self.cursor += 1;
+        self.cursor += self.step;
 
         let (x, y) = match self.dir {
             Direction::North => (self._fy(rev_cursor, 1), self._fx(rev_cursor, 1)),
@@ -163,3 +561,265 @@ impl Iterator for CoordGenerator {
         Some(Point::new(x, y))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_distances() {
+        let a = Point::new(-3, 4);
+        let b = Point::new(2, -1);
+
+        assert_eq!(a.manhattan_distance(&b), 10);
+        assert_eq!(b.manhattan_distance(&a), 10);
+        assert_eq!(a.chebyshev_distance(&b), 5);
+        assert_eq!(Point::new(-1, -7).chebyshev_distance(&Point::new(0, 0)), 7);
+
+        assert_eq!(a.manhattan_distance(&a), 0);
+        assert_eq!(a.chebyshev_distance(&a), 0);
+    }
+
+    #[test]
+    fn arithmetic() {
+        let p = Point::new(3, -2);
+        let v = Vector::new(-5, 7);
+
+        assert_eq!(p + v, (-2, 5));
+        assert_eq!(p - v, (8, -9));
+        assert_eq!(-v, (5, -7));
+        assert_eq!((p + v) - v, p);
+        assert_eq!(p + -v, p - v);
+        assert_eq!(p.shift(&v), p + v);
+        assert_eq!(p + Vector::from(Direction::North), (3, -1));
+    }
+
+    #[test]
+    fn scaling() {
+        assert_eq!(Vector::from(Direction::East) * 3, (3, 0));
+        assert_eq!(Vector::from(Direction::South) * -2, (0, 2));
+        assert_eq!(Vector::new(4, -7).scale(0), (0, 0));
+        assert_eq!(Vector::new(4, -7).scale(-1), -Vector::new(4, -7));
+    }
+
+    #[test]
+    fn rotations() {
+        let p = Point::new(3, -2);
+
+        assert_eq!(p.rotate_cw(), (-2, -3));
+        assert_eq!(p.rotate_ccw(), (2, 3));
+        assert_eq!(p.rotate_180(), (-3, 2));
+        assert_eq!(p.rotate_cw().rotate_cw().rotate_cw().rotate_cw(), p);
+        assert_eq!(p.rotate_ccw().rotate_cw(), p);
+        assert_eq!(p.rotate_cw().rotate_cw(), p.rotate_180());
+
+        for d in DirectionIterator::new() {
+            assert_eq!(d.rotate_cw().rotate_cw().rotate_cw().rotate_cw(), d);
+            assert_eq!(d.rotate_ccw().rotate_cw(), d);
+            assert_eq!(Vector::from(d.rotate_cw()), Vector::from(d).rotate_cw());
+            assert_eq!(Vector::from(d.rotate_ccw()), Vector::from(d).rotate_ccw());
+        }
+
+        assert_eq!(Direction::North.rotate_cw(), Direction::East);
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!("500,0".parse::<Point>(), Ok(Point::new(500, 0)));
+        assert_eq!("-3, 4".parse::<Point>(), Ok(Point::new(-3, 4)));
+        assert_eq!(" +7 ,-1 ".parse::<Point>(), Ok(Point::new(7, -1)));
+
+        assert!("1,2,3".parse::<Point>().is_err());
+        assert!("a,b".parse::<Point>().is_err());
+        assert!("12".parse::<Point>().is_err());
+        assert!(",".parse::<Point>().is_err());
+    }
+
+    #[test]
+    fn bounding_box() {
+        let points = [Point::new(-3, 2), Point::new(4, -5), Point::new(0, 0)];
+        let bb = BoundingBox::from_points(points).expect("Points given");
+
+        assert_eq!(bb.min, (-3, -5));
+        assert_eq!(bb.max, (4, 2));
+        assert_eq!(bb.width(), 8);
+        assert_eq!(bb.height(), 8);
+
+        assert!(bb.contains(&Point::new(-3, -5)));
+        assert!(bb.contains(&Point::new(4, 2)));
+        assert!(!bb.contains(&Point::new(-4, 0)));
+        assert!(!bb.contains(&Point::new(0, 3)));
+
+        let single = BoundingBox::from_points([Point::new(-1, -1)]).expect("Point given");
+        assert_eq!((single.width(), single.height()), (1, 1));
+
+        assert_eq!(BoundingBox::from_points(Vec::new()), None);
+    }
+
+    #[test]
+    fn clamp_to() {
+        let bb = BoundingBox::new(Point::new(-2, 0), Point::new(3, 5));
+
+        assert_eq!(Point::new(10, -7).clamp_to(&bb), Point::new(3, 0));
+        assert_eq!(Point::new(-9, 9).clamp_to(&bb), Point::new(-2, 5));
+        assert_eq!(Point::new(7, 2).clamp_to(&bb), Point::new(3, 2));
+        assert_eq!(Point::new(1, 1).clamp_to(&bb), Point::new(1, 1));
+    }
+
+    #[test]
+    fn direction_iterator_ends() {
+        let mut it = DirectionIterator::new();
+        assert_eq!(it.len(), 4);
+
+        assert_eq!(it.next(), Some(Direction::North));
+        assert_eq!(it.next_back(), Some(Direction::East));
+        assert_eq!(it.len(), 2);
+        assert_eq!(it.next_back(), Some(Direction::South));
+        assert_eq!(it.next(), Some(Direction::West));
+        assert_eq!(it.len(), 0);
+        assert_eq!(it.next(), None);
+        assert_eq!(it.next_back(), None);
+
+        assert_eq!(DirectionIterator::new().rev().collect::<Vec<_>>(), vec![
+            Direction::East, Direction::South, Direction::West, Direction::North,
+        ]);
+    }
+
+    #[test]
+    fn spiral() {
+        let order = CoordGenerator::spiral(Point::new(1, 1), 3, 3).collect::<Vec<_>>();
+        assert_eq!(order, vec![
+            Point::new(1, 1),
+            Point::new(2, 1),
+            Point::new(2, 2),
+            Point::new(1, 2),
+            Point::new(0, 2),
+            Point::new(0, 1),
+            Point::new(0, 0),
+            Point::new(1, 0),
+            Point::new(2, 0),
+        ]);
+
+        let corner = CoordGenerator::spiral(Point::new(0, 0), 2, 3).collect::<Vec<_>>();
+        assert_eq!(corner.len(), 6);
+        assert_eq!(corner[..3], [Point::new(0, 0), Point::new(1, 0), Point::new(1, 1)]);
+
+        assert_eq!(CoordGenerator::spiral(Point::new(0, 0), 0, 5).count(), 0);
+    }
+
+    #[test]
+    fn stepped() {
+        let points = CoordGenerator::with_step(&Direction::East, 4, 4, 2).collect::<Vec<_>>();
+        assert_eq!(points, [(0, 0), (2, 0), (0, 1), (2, 1), (0, 2), (2, 2), (0, 3), (2, 3)]
+            .map(Point::from));
+
+        let north = CoordGenerator::with_step(&Direction::North, 2, 3, 4).collect::<Vec<_>>();
+        assert_eq!(north, vec![Point::new(1, 2), Point::new(0, 1)]);
+
+        assert_eq!(CoordGenerator::with_step(&Direction::West, 3, 3, 10).count(), 1);
+    }
+
+    #[test]
+    fn deltas() {
+        for (d, v) in DirectionIterator::new().zip(DirectionIterator::DELTAS) {
+            assert_eq!(Vector::from(d), v);
+        }
+    }
+
+    #[test]
+    fn line_to() {
+        let line = |a: (isize, isize), b: (isize, isize)| {
+            Point::from(a).line_to(&Point::from(b)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(line((2, 1), (2, 3)), vec![Point::new(2, 1), Point::new(2, 2), Point::new(2, 3)]);
+        assert_eq!(line((0, 0), (-2, 0)), vec![Point::new(0, 0), Point::new(-1, 0), Point::new(-2, 0)]);
+        assert_eq!(line((1, 1), (3, -1)), vec![Point::new(1, 1), Point::new(2, 0), Point::new(3, -1)]);
+        assert_eq!(line((5, 5), (5, 5)), vec![Point::new(5, 5)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn line_to_skewed() {
+        let _ = Point::new(0, 0).line_to(&Point::new(1, 2));
+    }
+
+    #[test]
+    fn generic_i64() {
+        let p: Point<i64> = Point::new(4_000_000, -3);
+        let v: Vector<i64> = (2, 5).into();
+
+        assert_eq!(p + v, (4_000_002, 2));
+        assert_eq!((p + v) - v, p);
+        assert_eq!(p * 4_000_000, (16_000_000_000_000, -12_000_000));
+        assert_eq!(p.rotate_cw().rotate_ccw(), p);
+        assert_eq!(p.manhattan_distance(&Point::new(0, 0)), 4_000_003);
+        assert_eq!(Point::<i64>::new(0, 0).distance_from(&Point::new(3, -4)), 5.0);
+        assert_eq!("-7, 8".parse::<Point<i64>>(), Ok(Point::new(-7, 8)));
+        assert_eq!(Point::<i64>::new(0, 0).line_to(&Point::new(-2, 2)).last(),
+                   Some(Point::new(-2, 2)));
+    }
+
+    #[test]
+    fn generic_usize() {
+        let a: Point<usize> = Point::new(1, 7);
+        let b: Point<usize> = Point::new(4, 2);
+
+        assert_eq!(a.manhattan_distance(&b), 8);
+        assert_eq!(b.chebyshev_distance(&a), 5);
+        assert_eq!(a.shift(&Vector::new(2, 0)), (3, 7));
+        assert!("-1,2".parse::<Point<usize>>().is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let p = Point::new(-3, 14);
+        let json = serde_json::to_string(&p).expect("Cannot serialize point");
+        assert_eq!(json, "[-3,14]");
+        assert_eq!(serde_json::from_str::<Point>(&json).expect("Cannot read point"), p);
+
+        let json = serde_json::to_string(&Direction::North).expect("Cannot serialize direction");
+        assert_eq!(json, "\"north\"");
+        assert_eq!(serde_json::from_str::<Direction>("\"west\"").expect("Cannot read direction"),
+                   Direction::West);
+    }
+
+    #[test]
+    fn direction8_vectors() {
+        assert_eq!(Vector::from(Direction8::NorthEast), (1, 1));
+        assert_eq!(Vector::from(Direction8::NorthWest), (-1, 1));
+        assert_eq!(Vector::from(Direction8::SouthEast), (1, -1));
+        assert_eq!(Vector::from(Direction8::SouthWest), (-1, -1));
+
+        for d in DirectionIterator::new() {
+            assert_eq!(Vector::from(Direction8::from(d)), Vector::from(d));
+        }
+    }
+
+    #[test]
+    fn diagonals() {
+        let vectors = DiagonalIterator::new().map(Vector::from).collect::<Vec<_>>();
+        assert_eq!(vectors, vec![
+            Vector::new(-1, 1),
+            Vector::new(-1, -1),
+            Vector::new(1, -1),
+            Vector::new(1, 1),
+        ]);
+    }
+
+    #[test]
+    fn neighbors8_corner() {
+        let neighbors = Point::new(0, 0).neighbors8().collect::<Vec<_>>();
+        assert_eq!(neighbors, vec![
+            Point::new(0, 1),
+            Point::new(-1, 1),
+            Point::new(-1, 0),
+            Point::new(-1, -1),
+            Point::new(0, -1),
+            Point::new(1, -1),
+            Point::new(1, 0),
+            Point::new(1, 1),
+        ]);
+    }
+}
@@ -1,5 +1,6 @@
 use std::fs::File;
-use std::io::{BufRead, BufReader, Lines, Read};
+use std::io::{self, BufRead, BufReader, Lines, Read};
+use std::str::FromStr;
 
 struct LineReader<T>
         where T: BufRead {
@@ -20,15 +21,240 @@ pub fn lines(file: File) -> impl Iterator<Item = String> {
     LineReader { lines }
 }
 
+/* Opens the puzzle input, where ‹-› stands for standard input. With the
+ * ‹gzip› feature, files ending in ‹.gz› are decompressed on the fly. */
+pub fn open_input(name: &str) -> io::Result<Box<dyn BufRead>> {
+    open_input_or(name, io::stdin().lock())
+}
+
+fn open_input_or<R>(name: &str, stdin: R) -> io::Result<Box<dyn BufRead>>
+        where R: BufRead + 'static {
+    if name == "-" {
+        return Ok(Box::new(stdin));
+    }
+
+    Ok(decompress(name, File::open(name)?))
+}
+
+#[cfg(feature = "gzip")]
+fn decompress<R: Read + 'static>(name: &str, reader: R) -> Box<dyn BufRead> {
+    if name.ends_with(".gz") {
+        return Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(reader)));
+    }
+
+    Box::new(BufReader::new(reader))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn decompress<R: Read + 'static>(_name: &str, reader: R) -> Box<dyn BufRead> {
+    Box::new(BufReader::new(reader))
+}
+
+pub fn try_read_line<T>(lines: &mut Lines<T>) -> Option<io::Result<String>>
+        where T: BufRead {
+    lines.next()
+}
+
+/* Panicking shorthand for ‹try_read_line›. */
 pub fn read_line<T>(lines: &mut Lines<T>) -> Option<String>
         where T: BufRead {
-    Some(lines.next()?.expect("Cannot read line"))
+    Some(try_read_line(lines)?.expect("Cannot read line"))
+}
+
+/* Reads lines up to the first blank one (which is consumed) or the end of
+ * input, so that trailing empty lines do not end up as part of a grid. */
+pub fn read_nonblank_block<T>(lines: &mut Lines<T>) -> Vec<String>
+        where T: BufRead {
+    let mut block = Vec::new();
+
+    while let Some(line) = read_line(lines) {
+        if line.trim().is_empty() {
+            break;
+        }
+
+        block.push(line);
+    }
+
+    block
+}
+
+/* Yields runs of non-empty lines, treating any number of blank lines as
+ * a single separator. */
+pub fn groups(file: File) -> impl Iterator<Item = Vec<String>> {
+    groups_from(BufReader::new(file))
+}
+
+pub fn groups_from<R: BufRead>(reader: R) -> impl Iterator<Item = Vec<String>> {
+    numbered_groups_from(reader)
+        .map(|group| group.into_iter().map(|(_, line)| line).collect())
+}
+
+/* Like ‹groups_from›, with each line paired with its (1-based) number. */
+pub fn numbered_groups_from<R: BufRead>(reader: R)
+        -> impl Iterator<Item = Vec<(usize, String)>> {
+    let mut lines = LineReader { lines: reader.lines() }
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .peekable();
+
+    let blank = |(_, line): &(usize, String)| line.trim().is_empty();
+
+    std::iter::from_fn(move || {
+        while lines.next_if(blank).is_some() {}
+
+        let group = std::iter::from_fn(|| lines.next_if(|l| !blank(l)))
+            .collect::<Vec<(usize, String)>>();
+
+        if group.is_empty() { None } else { Some(group) }
+    })
+}
+
+/* Parses every non-empty line into ‹T›, stopping at the first failure. */
+pub fn parse_lines<T: FromStr>(file: File) -> Result<Vec<T>, T::Err> {
+    parse_lines_from(BufReader::new(file))
+}
+
+pub fn parse_lines_from<T, R>(reader: R) -> Result<Vec<T>, T::Err>
+        where T: FromStr, R: BufRead {
+    LineReader { lines: reader.lines() }
+        .filter(|line| !line.is_empty())
+        .map(|line| line.parse::<T>())
+        .collect()
+}
+
+/* Like ‹read_file›, but refuses inputs longer than ‹max_bytes› instead of
+ * reading them whole. */
+pub fn read_file_capped(file: File, max_bytes: usize) -> Result<String, String> {
+    read_capped_from(file, max_bytes)
+}
+
+pub fn read_capped_from<R: Read>(reader: R, max_bytes: usize) -> Result<String, String> {
+    let mut text = String::new();
+
+    BufReader::new(reader).take(max_bytes as u64 + 1)
+        .read_to_string(&mut text)
+        .map_err(|err| err.to_string())?;
+
+    if text.len() > max_bytes {
+        return Err(format!("Input exceeds {} bytes", max_bytes));
+    }
+
+    Ok(text)
 }
 
 pub fn read_file(file: File) -> Option<String> {
-    let mut reader = BufReader::new(file);
+    read_from(BufReader::new(file))
+}
+
+pub fn read_from<R: Read>(mut reader: R) -> Option<String> {
     let mut text = String::new();
 
     reader.read_to_string(&mut text).ok()?;
     Some(text)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aoc::matrix::Matrix;
+
+    #[test]
+    fn input_from_stdin() {
+        let stdin = io::Cursor::new("first\nsecond\n");
+        let input = open_input_or("-", stdin).expect("Stdin is always available");
+
+        assert_eq!(input.lines().map(Result::unwrap).collect::<Vec<_>>(), vec!["first", "second"]);
+        assert!(open_input_or("/nonexistent/input", io::empty()).is_err());
+    }
+
+    #[test]
+    fn blank_separated_groups() {
+        let groups = groups_from("1\n2\n\n3\n\n".as_bytes()).collect::<Vec<_>>();
+        assert_eq!(groups, vec![vec!["1", "2"], vec!["3"]]);
+
+        assert_eq!(groups_from("\n\na\n\n\nb".as_bytes()).count(), 2);
+        assert_eq!(groups_from("".as_bytes()).count(), 0);
+
+        let numbered = numbered_groups_from("\na\n\nb\nc".as_bytes()).collect::<Vec<_>>();
+        assert_eq!(numbered[1], vec![(4, String::from("b")), (5, String::from("c"))]);
+    }
+
+    #[test]
+    fn parse_integers() {
+        let numbers = parse_lines_from::<i32, _>("1\n-2\n\n30\n".as_bytes());
+        assert_eq!(numbers, Ok(vec![1, -2, 30]));
+    }
+
+    #[test]
+    fn parse_error() {
+        let numbers = parse_lines_from::<i32, _>("1\ntwo\n3\n".as_bytes());
+        assert!(numbers.is_err());
+    }
+
+    #[test]
+    fn plain_input() {
+        let input = decompress("input.txt", io::Cursor::new("a\nb\n"));
+        assert_eq!(input.lines().map(Result::unwrap).collect::<Vec<_>>(), vec!["a", "b"]);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip_input() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"first\nsecond\n").unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let input = decompress("input.gz", io::Cursor::new(compressed));
+        assert_eq!(input.lines().map(Result::unwrap).collect::<Vec<_>>(), vec!["first", "second"]);
+    }
+
+    /* Yields one line and then fails. */
+    struct Faulty(bool);
+
+    impl Read for Faulty {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0 {
+                return Err(io::Error::other("Broken pipe"));
+            }
+
+            self.0 = true;
+            buf[.. 6].copy_from_slice(b"first\n");
+            Ok(6)
+        }
+    }
+
+    #[test]
+    fn read_line_error() {
+        let mut lines = BufReader::new(Faulty(false)).lines();
+
+        assert_eq!(try_read_line(&mut lines).map(Result::unwrap), Some(String::from("first")));
+        assert!(try_read_line(&mut lines).is_some_and(|r| r.is_err()));
+    }
+
+    #[test]
+    fn capped() {
+        assert_eq!(read_capped_from("0123456789".as_bytes(), 10), Ok(String::from("0123456789")));
+        assert!(read_capped_from("0123456789".as_bytes(), 9).is_err());
+        assert_eq!(read_capped_from("".as_bytes(), 0), Ok(String::new()));
+    }
+
+    #[test]
+    fn nonblank_block() {
+        let mut lines = "123\n456\n\n789\n".as_bytes().lines();
+
+        assert_eq!(read_nonblank_block(&mut lines), vec!["123", "456"]);
+        assert_eq!(read_nonblank_block(&mut lines), vec!["789"]);
+        assert!(read_nonblank_block(&mut lines).is_empty());
+    }
+
+    #[test]
+    fn nonblank_block_grid() {
+        let block = read_nonblank_block(&mut "123\n456\n\n".as_bytes().lines());
+        let m = Matrix::from_lines(&block, |c| c.to_digit(10).ok_or(String::from("digit")));
+
+        assert_eq!(m.map(|m| (m.width, m.height)), Ok((3, 2)));
+    }
+}
@@ -1,15 +1,15 @@
 use crate::aoc::euclid::*;
 use std::ops::{Index, IndexMut};
 
-#[derive(Debug)]
-pub struct Matrix<T: Default + Ord> {
+#[derive(Debug, Clone)]
+pub struct Matrix<T: Default> {
     pub width: usize,
     pub height: usize,
     pub data: Vec<Vec<T>>,
 }
 
 impl<T> Matrix<T>
-        where T: Default + Ord {
+        where T: Default {
     pub fn new(width: usize, height: usize) -> Matrix<T> {
         let mut data: Vec<Vec<T>> = Vec::with_capacity(height);
 
@@ -22,25 +22,191 @@ impl<T> Matrix<T>
         Self { width, height, data }
     }
 
+    /* Builds a matrix from equally long lines, converting each character
+     * with ‹f›; conversion errors are prefixed by the offending position. */
+    pub fn from_lines<F>(lines: &[String], f: F) -> Result<Matrix<T>, String>
+            where F: Fn(char) -> Result<T, String> {
+        let width = match lines.first() {
+            Some(line) => line.chars().count(),
+            None => return Err(String::from("Empty input")),
+        };
+
+        if width == 0 {
+            return Err(String::from("Empty input"));
+        }
+
+        if let Some(y) = lines.iter().position(|l| l.chars().count() != width) {
+            return Err(format!("Line {} does not have length {}", y + 1, width));
+        }
+
+        let mut data: Vec<Vec<T>> = Vec::with_capacity(lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            data.push(line.chars().enumerate()
+                .map(|(x, c)| f(c).map_err(|err| format!("[{}, {}]: {}", x, y, err)))
+                .collect::<Result<Vec<T>, String>>()?);
+        }
+
+        Ok(Self { width, height: lines.len(), data })
+    }
+
     pub fn contains(&self, p: &Point) -> bool {
         p.x >= 0 && p.x < self.width as isize
             && p.y >= 0 && p.y < self.height as isize
     }
 
+    pub fn iter_cells(&self) -> impl Iterator<Item = (Point, &T)> + '_ {
+        self.data.iter().enumerate().flat_map(|(y, row)| {
+            row.iter().enumerate().map(move |(x, cell)| (Point::new(x as isize, y as isize), cell))
+        })
+    }
+
+    pub fn iter_cells_mut(&mut self) -> impl Iterator<Item = (Point, &mut T)> + '_ {
+        self.data.iter_mut().enumerate().flat_map(|(y, row)| {
+            row.iter_mut().enumerate().map(move |(x, cell)| (Point::new(x as isize, y as isize), cell))
+        })
+    }
+
+    pub fn row(&self, y: usize) -> Option<&[T]> {
+        self.data.get(y).map(|row| row.as_slice())
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> + '_ {
+        self.data.iter().map(|row| row.as_slice())
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> + '_ {
+        self.data.iter().filter_map(move |row| row.get(x))
+    }
+
+    pub fn get(&self, p: Point) -> Option<&T> {
+        if self.contains(&p) { Some(&self[p]) } else { None }
+    }
+
+    pub fn get_mut(&mut self, p: Point) -> Option<&mut T> {
+        if self.contains(&p) { Some(&mut self[p]) } else { None }
+    }
+
+    pub fn get_xy(&self, x: usize, y: usize) -> Option<&T> {
+        self.data.get(y)?.get(x)
+    }
+
+    pub fn neighbors4(&self, p: Point) -> impl Iterator<Item = (Point, &T)> + '_ {
+        DirectionIterator::DELTAS.iter()
+            .map(move |d| p.shift(d))
+            .filter_map(|q| Some((q, self.get(q)?)))
+    }
+
+    pub fn neighbors8(&self, p: Point) -> impl Iterator<Item = (Point, &T)> + '_ {
+        p.neighbors8().filter_map(|q| Some((q, self.get(q)?)))
+    }
+
+    /* Walks from ‹start› in steps of ‹dir› while inside the matrix; a zero
+     * step would never leave it. */
+    pub fn diagonal(&self, start: Point, dir: Vector) -> impl Iterator<Item = (Point, &T)> + '_ {
+        assert!(dir != Vector::new(0, 0), "Direction must be non-zero");
+
+        std::iter::successors(Some(start), move |p| Some(p.shift(&dir)))
+            .take_while(|p| self.contains(p))
+            .map(|p| (p, &self[p]))
+    }
+
+    pub fn map<U: Default, F: Fn(&T) -> U>(&self, f: F) -> Matrix<U> {
+        let data = self.data.iter()
+            .map(|row| row.iter().map(&f).collect())
+            .collect();
+
+        Matrix { width: self.width, height: self.height, data }
+    }
+
+    /* Renders the matrix as rows of characters joined by newlines, without
+     * a trailing one. */
+    pub fn render<F: Fn(&T) -> char>(&self, f: F) -> String {
+        self.data.iter()
+            .map(|row| row.iter().map(&f).collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
     pub fn fold<F: Fn(&T, &T) -> T>(a: &Self, b: &Self, f: F) -> Self {
-        assert_eq!(a.width, b.width);
-        assert_eq!(a.height, b.height);
+        Self::try_fold(a, b, f).expect("Matrix dimensions differ")
+    }
+
+    pub fn try_fold<F: Fn(&T, &T) -> T>(a: &Self, b: &Self, f: F) -> Result<Self, String> {
+        if a.width != b.width || a.height != b.height {
+            return Err(format!("Cannot fold {}×{} with {}×{} matrix",
+                               a.width, a.height, b.width, b.height));
+        }
 
         let mut result = Self::new(a.width, a.height);
         for xy in CoordGenerator::new(&Direction::East, a.width, a.height) {
             result[xy] = f(&a[xy], &b[xy]);
         }
 
-        result
+        Ok(result)
     }
 }
 
-impl<T: Default + Ord> Index<Point> for Matrix<T> {
+impl<T> Matrix<T>
+        where T: Default + Clone {
+    /* Mirrors the matrix along its vertical axis, i.e. reverses each row. */
+    pub fn flip_horizontal(&self) -> Matrix<T> {
+        let data = self.data.iter()
+            .map(|row| row.iter().rev().cloned().collect())
+            .collect();
+
+        Self { width: self.width, height: self.height, data }
+    }
+
+    /* Mirrors the matrix along its horizontal axis, i.e. reverses the rows. */
+    pub fn flip_vertical(&self) -> Matrix<T> {
+        let data = self.data.iter().rev().cloned().collect();
+
+        Self { width: self.width, height: self.height, data }
+    }
+
+    /* Swaps rows with columns, so the result is ‹height›×‹width›. */
+    pub fn transpose(&self) -> Matrix<T> {
+        let data = (0 .. self.width)
+            .map(|x| self.column(x).cloned().collect())
+            .collect();
+
+        Self { width: self.height, height: self.width, data }
+    }
+}
+
+impl<T> Matrix<T>
+        where T: Default + Clone + PartialEq {
+    /* Replaces every passable cell 4-connected to ‹start› with ‹fill›. */
+    pub fn flood_fill<F: Fn(&T) -> bool>(&mut self, start: Point, fill: T, passable: F) {
+        if !self.get(start).is_some_and(&passable) {
+            return;
+        }
+
+        let mut seen = self.map(|_| false);
+        let mut stack = vec![start];
+        seen[start] = true;
+
+        while let Some(p) = stack.pop() {
+            self[p] = fill.clone();
+
+            for d in DirectionIterator::DELTAS.iter() {
+                let next = p.shift(d);
+                if self.get(next).is_some_and(&passable) && !seen[next] {
+                    seen[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+    }
+}
+
+impl<T: Default> Default for Matrix<T> {
+    fn default() -> Self {
+        Self::new(0, 0)
+    }
+}
+
+impl<T: Default> Index<Point> for Matrix<T> {
     type Output = T;
 
     fn index(&self, index: Point) -> &Self::Output {
@@ -48,8 +214,267 @@ impl<T: Default + Ord> Index<Point> for Matrix<T> {
     }
 }
 
-impl<T: Default + Ord> IndexMut<Point> for Matrix<T> {
+impl<T: Default> Index<(usize, usize)> for Matrix<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
+        &self.data[y][x]
+    }
+}
+
+impl<T: Default> IndexMut<(usize, usize)> for Matrix<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Self::Output {
+        &mut self.data[y][x]
+    }
+}
+
+impl<T: Default> IndexMut<Point> for Matrix<T> {
     fn index_mut(&mut self, index: Point) -> &mut Self::Output {
         &mut self.data[index.y as usize][index.x as usize]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Matrix<u32> {
+        Matrix {
+            width: 3,
+            height: 3,
+            data: vec![
+                vec![1, 2, 3],
+                vec![4, 5, 6],
+                vec![7, 8, 9],
+            ],
+        }
+    }
+
+    #[test]
+    fn tuple_index() {
+        let mut m = example();
+
+        assert_eq!(m[(2, 1)], 6);
+        assert_eq!(m[(2, 1)], m[Point::new(2, 1)]);
+        assert_eq!(m.get_xy(0, 2), Some(&7));
+        assert_eq!(m.get_xy(3, 0), None);
+        assert_eq!(m.get_xy(0, 3), None);
+
+        m[(1, 2)] = 42;
+        assert_eq!(m[Point::new(1, 2)], 42);
+    }
+
+    #[test]
+    fn cells() {
+        let mut m = example();
+
+        for (p, value) in m.iter_cells() {
+            assert_eq!(*value, (3 * p.y + p.x + 1) as u32);
+        }
+
+        assert_eq!(m.iter_cells().next(), Some((Point::new(0, 0), &1)));
+        assert_eq!(m.iter_cells().count(), 9);
+
+        for (p, value) in m.iter_cells_mut() {
+            *value = (10 * p.x + p.y) as u32;
+        }
+
+        assert_eq!(m[(2, 1)], 21);
+    }
+
+    #[test]
+    fn checked_get() {
+        let mut m = example();
+
+        assert_eq!(m.get(Point::new(1, 2)), Some(&8));
+        assert_eq!(m.get(Point::new(-1, 0)), None);
+        assert_eq!(m.get(Point::new(0, -1)), None);
+        assert_eq!(m.get(Point::new(3, 0)), None);
+        assert_eq!(m.get(Point::new(0, 3)), None);
+        assert_eq!(m.get(Point::new(isize::MIN, isize::MAX)), None);
+
+        *m.get_mut(Point::new(2, 2)).expect("Point is inside") = 0;
+        assert_eq!(m[(2, 2)], 0);
+        assert!(m.get_mut(Point::new(-5, 1)).is_none());
+    }
+
+    #[test]
+    fn from_lines() {
+        let lines = vec![String::from("123"), String::from("456"), String::from("789")];
+        let m = Matrix::from_lines(&lines, |c| c.to_digit(10).ok_or(format!("Not a digit: {}", c)))
+            .expect("Lines are valid");
+
+        assert_eq!(m.data, example().data);
+
+        let bad = vec![String::from("12"), String::from("3x")];
+        assert_eq!(
+            Matrix::from_lines(&bad, |c| c.to_digit(10).ok_or(format!("Not a digit: {}", c)))
+                .unwrap_err(),
+            "[1, 1]: Not a digit: x");
+    }
+
+    #[test]
+    fn from_lines_ragged() {
+        let lines = vec![String::from("123"), String::from("45"), String::from("789")];
+        let err = Matrix::<u32>::from_lines(&lines, |c| Ok(c as u32)).unwrap_err();
+
+        assert_eq!(err, "Line 2 does not have length 3");
+    }
+
+    #[test]
+    fn from_lines_empty() {
+        assert!(Matrix::<u32>::from_lines(&[], |c| Ok(c as u32)).is_err());
+        assert!(Matrix::<u32>::from_lines(&[String::new()], |c| Ok(c as u32)).is_err());
+    }
+
+    #[test]
+    fn render() {
+        let mut m = Matrix::<bool>::new(3, 2);
+        m[(0, 0)] = true;
+        m[(2, 1)] = true;
+
+        assert_eq!(m.render(|&b| if b { '#' } else { '.' }), "#..\n..#");
+    }
+
+    #[test]
+    fn flips() {
+        let m = example();
+        let h = m.flip_horizontal();
+        let v = m.flip_vertical();
+
+        assert_eq!(h.data, vec![vec![3, 2, 1], vec![6, 5, 4], vec![9, 8, 7]]);
+        assert_eq!(h.flip_horizontal().data, m.data);
+        assert_eq!(v.flip_vertical().data, m.data);
+
+        assert_eq!((h[(0, 0)], h[(2, 0)], h[(0, 2)], h[(2, 2)]), (3, 1, 9, 7));
+        assert_eq!((v[(0, 0)], v[(2, 0)], v[(0, 2)], v[(2, 2)]), (7, 9, 1, 3));
+    }
+
+    #[test]
+    fn unordered_cells() {
+        #[derive(Debug, Default, Clone, PartialEq)]
+        struct Cell {
+            weight: f64,
+        }
+
+        let mut m = Matrix::<Cell>::new(2, 2);
+        m[(1, 0)].weight = 0.5;
+
+        assert_eq!(m.get(Point::new(1, 0)), Some(&Cell { weight: 0.5 }));
+        assert_eq!(m.flip_horizontal()[(0, 0)], Cell { weight: 0.5 });
+    }
+
+    #[test]
+    fn rows_and_columns() {
+        let mut m = Matrix::<u32>::new(3, 2);
+        for (p, cell) in m.iter_cells_mut() {
+            *cell = (p.y * 3 + p.x) as u32;
+        }
+
+        assert_eq!(m.row(1), Some(&[3, 4, 5][..]));
+        assert_eq!(m.row(2), None);
+        assert_eq!(m.rows().count(), 2);
+        assert_eq!(m.column(2).copied().collect::<Vec<u32>>(), vec![2, 5]);
+        assert_eq!(m.column(3).count(), 0);
+    }
+
+    #[test]
+    fn map() {
+        let odd = example().map(|n| n % 2 == 1);
+
+        assert_eq!((odd.width, odd.height), (3, 3));
+        assert_eq!(odd.render(|&b| if b { '1' } else { '0' }), "101\n010\n101");
+    }
+
+    #[test]
+    fn transpose() {
+        let mut m = Matrix::<u32>::new(3, 2);
+        for (p, cell) in m.iter_cells_mut() {
+            *cell = (p.y * 3 + p.x) as u32;
+        }
+
+        let t = m.transpose();
+        assert_eq!((t.width, t.height), (2, 3));
+        assert_eq!(t.data, vec![vec![0, 3], vec![1, 4], vec![2, 5]]);
+        assert_eq!(t[(1, 2)], m[(2, 1)]);
+
+        let tt = t.transpose();
+        assert_eq!((tt.width, tt.height), (3, 2));
+        assert_eq!(tt.data, m.data);
+    }
+
+    #[test]
+    fn flood_fill() {
+        let lines = [".#..", ".#..", "##..", "...."].map(String::from);
+        let mut m = Matrix::from_lines(&lines, Ok).unwrap();
+
+        m.flood_fill(Point::new(0, 0), 'o', |&c| c == '.');
+        assert_eq!(m.render(|&c| c), "o#..\no#..\n##..\n....");
+
+        m.flood_fill(Point::new(1, 0), 'x', |&c| c == '.');
+        assert_eq!(m.render(|&c| c), "o#..\no#..\n##..\n....");
+
+        m.flood_fill(Point::new(3, 3), '~', |&c| c == '.');
+        assert_eq!(m.render(|&c| c), "o#~~\no#~~\n##~~\n~~~~");
+    }
+
+    #[test]
+    fn neighbors() {
+        let m = example();
+
+        let corner = m.neighbors4(Point::new(0, 0)).map(|(_, v)| *v).collect::<Vec<u32>>();
+        assert_eq!(corner, vec![4, 2]);
+        assert_eq!(m.neighbors8(Point::new(0, 0)).count(), 3);
+
+        let mut inner = m.neighbors4(Point::new(1, 1)).map(|(_, v)| *v).collect::<Vec<u32>>();
+        inner.sort();
+        assert_eq!(inner, vec![2, 4, 6, 8]);
+        assert_eq!(m.neighbors8(Point::new(1, 1)).count(), 8);
+    }
+
+    #[test]
+    fn empty_default() {
+        let m = Matrix::<u32>::default();
+
+        assert_eq!((m.width, m.height), (0, 0));
+        assert!(!m.contains(&Point::new(0, 0)));
+        assert!(!m.contains(&Point::new(-1, 3)));
+        assert_eq!(m.iter_cells().count(), 0);
+    }
+
+    #[test]
+    fn try_fold() {
+        let a = Matrix::<u32>::new(2, 2);
+        let b = Matrix::<u32>::new(3, 3);
+
+        assert!(Matrix::try_fold(&a, &b, |p, q| p + q).is_err());
+
+        let sum = Matrix::try_fold(&example(), &example(), |p, q| p + q)
+            .expect("Dimensions match");
+        assert_eq!(sum.data[2], vec![14, 16, 18]);
+    }
+
+    #[test]
+    fn diagonal() {
+        let m = example();
+        let diag = m.diagonal(Point::new(0, 0), Vector::new(1, 1))
+            .map(|(p, v)| (p, *v))
+            .collect::<Vec<_>>();
+
+        assert_eq!(diag, vec![
+            (Point::new(0, 0), 1),
+            (Point::new(1, 1), 5),
+            (Point::new(2, 2), 9),
+        ]);
+
+        assert_eq!(m.diagonal(Point::new(2, 0), Vector::new(-1, 1))
+                        .map(|(_, v)| *v).collect::<Vec<_>>(), vec![3, 5, 7]);
+        assert_eq!(m.diagonal(Point::new(3, 3), Vector::new(1, 1)).count(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn diagonal_zero_step() {
+        let _ = example().diagonal(Point::new(1, 1), Vector::new(0, 0));
+    }
+}
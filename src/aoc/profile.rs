@@ -0,0 +1,139 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/* Allocator wrapping the system one that keeps track of how many allocations
+ * were made and how much memory was in use at most. It is only installed as
+ * the global allocator with the ‹profile› feature, so that regular builds do
+ * not pay for the bookkeeping. */
+pub struct Counting {
+    allocations: AtomicUsize,
+    bytes: AtomicUsize,
+    current: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub allocations: usize,
+    pub bytes: usize,
+    pub peak: usize,
+}
+
+impl Counting {
+    pub const fn new() -> Self {
+        Self {
+            allocations: AtomicUsize::new(0),
+            bytes: AtomicUsize::new(0),
+            current: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            allocations: self.allocations.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            peak: self.peak.load(Ordering::Relaxed),
+        }
+    }
+
+    fn record(&self, size: usize) {
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(size, Ordering::Relaxed);
+
+        let current = self.current.fetch_add(size, Ordering::Relaxed) + size;
+        self.peak.fetch_max(current, Ordering::Relaxed);
+    }
+}
+
+impl Default for Counting {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            self.record(layout.size());
+        }
+
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        self.current.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new.is_null() {
+            self.current.fetch_sub(layout.size(), Ordering::Relaxed);
+            self.record(new_size);
+        }
+
+        new
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} allocations, {} bytes allocated, {} bytes at peak",
+               self.allocations, self.bytes, self.peak)
+    }
+}
+
+#[cfg(feature = "profile")]
+#[global_allocator]
+static ALLOCATOR: Counting = Counting::new();
+
+/* Statistics of the global allocator, if profiling is compiled in. */
+pub fn stats() -> Option<Stats> {
+    #[cfg(feature = "profile")]
+    return Some(ALLOCATOR.stats());
+
+    #[cfg(not(feature = "profile"))]
+    None
+}
+
+/* Prints the statistics to stderr; ‹--profile› is only accepted in builds
+ * where there are some to print. */
+pub fn report() {
+    if let Some(stats) = stats() {
+        eprintln!("Profile: {}", stats);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_direct() {
+        let counting = Counting::new();
+        let layout = Layout::array::<u64>(4).unwrap();
+
+        unsafe {
+            let ptr = counting.alloc(layout);
+            assert!(!ptr.is_null());
+            counting.dealloc(ptr, layout);
+        }
+
+        assert_eq!(counting.stats(), Stats { allocations: 1, bytes: 32, peak: 32 });
+    }
+
+    #[cfg(feature = "profile")]
+    #[test]
+    fn counts_vec_push() {
+        let before = stats().expect("Profiling is enabled").allocations;
+
+        let mut v = std::hint::black_box(Vec::<u32>::new());
+        v.push(1);
+
+        assert!(stats().unwrap().allocations > before);
+        assert_eq!(v, [1]);
+    }
+}
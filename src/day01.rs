@@ -1,58 +1,69 @@
 use aoc::args::Puzzle;
-use std::fs::File;
-use std::io::Result as IOResult;
-use std::io::{BufRead, BufReader, Lines};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::io::BufRead;
 
 type Pack = Vec<u32>;
 type Expedition = Vec<Pack>;
 
-fn read_pack<T>(lines: &mut Lines<T>) -> Option<Pack>
-        where T: BufRead {
-    let mut it = lines
-        .map(|val| val.unwrap())
-        .take_while(|line| !line.is_empty())
-        .peekable();
-
-    it.peek()?;
+fn read_pack(lines: &[(usize, String)]) -> Result<Pack, String> {
+    lines.iter()
+        .map(|(lineno, line)| line.trim().parse()
+             .map_err(|_| format!("{}: Invalid calories: {}", lineno, line)))
+        .collect()
+}
 
-    Some(
-        it.map(|line| line.parse().expect("Cannot parse value"))
-            .collect(),
-    )
+fn read_expedition<R: BufRead>(input: R) -> Result<Expedition, String> {
+    aoc::io::numbered_groups_from(input).map(|group| read_pack(&group)).collect()
 }
 
-fn read_expedition(file: &File) -> Expedition {
-    let mut lines = BufReader::new(file).lines();
-    let mut packs = Expedition::new();
+/* Sum of the ‹n› largest packs, keeping only ‹n› of them in memory. */
+fn top_n_calories(n: usize, packs: impl Iterator<Item = u32>) -> u32 {
+    let mut heap = BinaryHeap::<Reverse<u32>>::with_capacity(n + 1);
+
+    for pack in packs {
+        heap.push(Reverse(pack));
 
-    while let Some(pack) = read_pack(&mut lines) {
-        packs.push(pack);
+        if heap.len() > n {
+            heap.pop();
+        }
     }
 
-    packs
+    heap.iter().map(|Reverse(pack)| pack).sum()
 }
 
 fn sum_calories(n: usize, expedition: &Expedition) -> u32 {
     assert!(expedition.len() >= n);
 
-    let mut sorted_expedition: Vec<u32> = expedition.iter()
-        .map(|v| v.iter().sum())
+    top_n_calories(n, expedition.iter().map(|v| v.iter().sum()))
+}
+
+/* 1-based indices and totals of the ‹n› best elves, best first. */
+#[allow(dead_code)]
+fn top_elves(n: usize, expedition: &Expedition) -> Vec<(usize, u32)> {
+    let mut totals: Vec<(usize, u32)> = expedition.iter()
+        .enumerate()
+        .map(|(i, pack)| (i + 1, pack.iter().sum()))
         .collect();
 
-    sorted_expedition.sort_by(|a, b| b.cmp(a));
+    totals.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    totals.truncate(n);
+    totals
+}
 
-    sorted_expedition.iter().take(n).sum()
+fn solve(expedition: &Expedition, puzzle: Puzzle) -> u32 {
+    match puzzle {
+        Puzzle::P1 => sum_calories(1, expedition),
+        Puzzle::P2 => sum_calories(3, expedition),
+    }
 }
 
-fn main() -> IOResult<()> {
+fn main() -> Result<(), String> {
     let args = aoc::args::Arguments::parse();
-    let file = File::open(args.file_name)?;
+    let expedition = read_expedition(args.open()?)?;
 
-    let expedition = read_expedition(&file);
-
-    match args.puzzle {
-        Puzzle::P1 => println!("{}", sum_calories(1, &expedition)),
-        Puzzle::P2 => println!("{}", sum_calories(3, &expedition)),
+    for puzzle in args.puzzles() {
+        println!("{}", solve(&expedition, puzzle));
     }
 
     Ok(())
@@ -74,11 +85,38 @@ mod tests {
 
     #[test]
     fn p1_example1() {
-        assert_eq!(sum_calories(1, &example1()), 24000);
+        assert_eq!(solve(&example1(), Puzzle::P1), 24000);
+    }
+
+    #[test]
+    fn streaming_matches_sorting() {
+        let mut sorted = example1().iter().map(|v| v.iter().sum()).collect::<Vec<u32>>();
+        sorted.sort_by(|a, b| b.cmp(a));
+
+        for n in [1, 3] {
+            let packs = example1().into_iter().map(|v| v.iter().sum());
+            assert_eq!(top_n_calories(n, packs), sorted.iter().take(n).sum::<u32>());
+        }
+    }
+
+    #[test]
+    fn malformed_line() {
+        let err = read_expedition("12\nabc\n".as_bytes()).expect_err("Line 2 is invalid");
+        assert!(err.contains("abc"), "{}", err);
+        assert!(err.starts_with("2:"), "{}", err);
+
+        let ok = read_expedition("1\n2\n\n3\n".as_bytes()).expect("Input is valid");
+        assert_eq!(ok, vec![vec![1, 2], vec![3]]);
+    }
+
+    #[test]
+    fn top_elves() {
+        assert_eq!(super::top_elves(1, &example1()), vec![(4, 24000)]);
+        assert_eq!(super::top_elves(3, &example1()), vec![(4, 24000), (3, 11000), (5, 10000)]);
     }
 
     #[test]
     fn p2_example1() {
-        assert_eq!(sum_calories(3, &example1()), 45000);
+        assert_eq!(solve(&example1(), Puzzle::P2), 45000);
     }
 }
@@ -1,7 +1,6 @@
 use aoc::args::Puzzle;
-use std::fs::File;
-use std::io::Result as IOResult;
-use std::io::{BufRead, BufReader};
+use clap::{Args, ValueEnum};
+use std::io::{BufRead, Lines};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum Shape {
@@ -10,6 +9,22 @@ enum Shape {
     Scissors = 3,
 }
 
+impl Shape {
+    /* The shape this one wins against. */
+    fn beats(self) -> Shape {
+        match self {
+            Shape::Rock => Shape::Scissors,
+            Shape::Paper => Shape::Rock,
+            Shape::Scissors => Shape::Paper,
+        }
+    }
+
+    /* The shape this one loses against. */
+    fn loses_to(self) -> Shape {
+        self.beats().beats()
+    }
+}
+
 #[derive(PartialEq, Eq)]
 enum MatchResult {
     Lose = 0,
@@ -17,13 +32,25 @@ enum MatchResult {
     Win = 6,
 }
 
+impl MatchResult {
+    /* The same match, as seen by the other player. */
+    #[allow(clippy::wrong_self_convention)]
+    fn from_opponent(&self) -> MatchResult {
+        match self {
+            MatchResult::Lose => MatchResult::Win,
+            MatchResult::Draw => MatchResult::Draw,
+            MatchResult::Win => MatchResult::Lose,
+        }
+    }
+}
+
 fn cmp_round(left: &Shape, right: &Shape) -> MatchResult {
-    match (left, right) {
-        (x, y) if x == y => MatchResult::Draw,
-        (Shape::Rock, Shape::Scissors)
-        | (Shape::Scissors, Shape::Paper)
-        | (Shape::Paper, Shape::Rock) => MatchResult::Lose,
-        _ => MatchResult::Win,
+    if left.beats() == *right {
+        MatchResult::Lose
+    } else if left.loses_to() == *right {
+        MatchResult::Win
+    } else {
+        MatchResult::Draw
     }
 }
 
@@ -33,91 +60,153 @@ fn eval_round(r: &Round) -> usize {
     (r.1 as usize) + (cmp_round(&r.0, &r.1) as usize)
 }
 
+#[allow(dead_code)]
+fn opponent_score(r: &Round) -> usize {
+    (r.0 as usize) + (cmp_round(&r.0, &r.1).from_opponent() as usize)
+}
+
 type Strategy = Vec<Round>;
 
+fn score_breakdown(s: &Strategy) -> Vec<usize> {
+    s.iter().map(eval_round).collect()
+}
+
 fn eval_strategy(s: &Strategy) -> usize {
-    s.iter().map(eval_round).sum()
+    score_breakdown(s).iter().sum()
 }
 
-fn parse_shape(part: &str) -> Option<Shape> {
+fn parse_shape(part: &str) -> Result<Shape, String> {
     match part {
-        "A" | "X" => Some(Shape::Rock),
-        "B" | "Y" => Some(Shape::Paper),
-        "C" | "Z" => Some(Shape::Scissors),
-        _ => None,
+        "A" | "X" => Ok(Shape::Rock),
+        "B" | "Y" => Ok(Shape::Paper),
+        "C" | "Z" => Ok(Shape::Scissors),
+        _ => Err(format!("Invalid shape code: {}", part)),
     }
 }
 
-fn parse_result(part: &str) -> Option<MatchResult> {
+fn parse_result(part: &str) -> Result<MatchResult, String> {
     match part {
-        "X" => Some(MatchResult::Lose),
-        "Y" => Some(MatchResult::Draw),
-        "Z" => Some(MatchResult::Win),
-        _ => None,
+        "X" => Ok(MatchResult::Lose),
+        "Y" => Ok(MatchResult::Draw),
+        "Z" => Ok(MatchResult::Win),
+        _ => Err(format!("Invalid result code: {}", part)),
     }
 }
 
-fn read_parts(line: &str) -> Option<(&str, &str)> {
-    let parts: Vec<&str> = line.split(' ').collect();
+fn read_parts(line: &str) -> Result<(&str, &str), String> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
 
     if parts.len() != 2 {
-        panic!("Invalid input");
+        return Err(format!("Expected 2 codes, got {}: {}", parts.len(), line));
     }
 
-    Some((parts[0], parts[1]))
+    Ok((parts[0], parts[1]))
 }
 
-fn read_round_1(line: &str) -> Option<Round> {
+fn read_round_1(line: &str) -> Result<Round, String> {
     let (sl, sr) = read_parts(line)?;
 
-    let left = parse_shape(sl);
-    let right = parse_shape(sr);
-
-    left.zip(right)
+    Ok((parse_shape(sl)?, parse_shape(sr)?))
 }
 
 fn find_match(left: &Shape, expected: &MatchResult) -> Shape {
-    for shape in [Shape::Rock, Shape::Paper, Shape::Scissors] {
-        if cmp_round(left, &shape) == *expected {
-            return shape;
-        }
+    match expected {
+        MatchResult::Lose => left.beats(),
+        MatchResult::Draw => *left,
+        MatchResult::Win => left.loses_to(),
     }
-
-    panic!("BUG: find_match(): Exhausted shape search space")
 }
 
-fn read_round_2(line: &str) -> Option<Round> {
+fn read_round_2(line: &str) -> Result<Round, String> {
     let (sl, sr) = read_parts(line)?;
 
     let left = parse_shape(sl)?;
     let expected = parse_result(sr)?;
 
-    Some((left, find_match(&left, &expected)))
+    Ok((left, find_match(&left, &expected)))
+}
+
+/* How to interpret the second column of the strategy guide. */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Encoding {
+    Shapes,
+    Outcomes,
 }
 
-fn read_strategy(file: &File, reader: &dyn Fn(&str) -> Option<Round>) -> Strategy {
-    let mut lines = BufReader::new(file).lines();
+impl Encoding {
+    fn reader(&self) -> fn(&str) -> Result<Round, String> {
+        match self {
+            Encoding::Shapes => read_round_1,
+            Encoding::Outcomes => read_round_2,
+        }
+    }
+}
+
+impl From<&Puzzle> for Encoding {
+    fn from(puzzle: &Puzzle) -> Self {
+        match puzzle {
+            Puzzle::P1 => Encoding::Shapes,
+            Puzzle::P2 => Encoding::Outcomes,
+        }
+    }
+}
+
+fn parse_strategy<T>(lines: &mut Lines<T>, encoding: Encoding) -> Result<Strategy, String>
+        where T: BufRead {
+    let reader = encoding.reader();
     let mut strategy = Strategy::new();
+    let mut lineno: usize = 0;
+
+    while let Some(line) = aoc::io::read_line(lines) {
+        lineno += 1;
 
-    while let Some(line) = aoc::io::read_line(&mut lines) {
-        if let Some(round) = reader(&line) {
-            strategy.push(round);
+        if line.trim().is_empty() {
+            continue;
         }
+
+        strategy.push(reader(&line).map_err(|err| format!("{}: {}", lineno, err))?);
     }
 
-    strategy
+    Ok(strategy)
+}
+
+fn read_strategy(guide: &str, encoding: Encoding) -> Result<Strategy, String> {
+    parse_strategy(&mut guide.as_bytes().lines(), encoding)
+}
+
+/* Total score of the guide, with the second column read as the puzzle says. */
+fn solve(guide: &str, puzzle: Puzzle) -> Result<usize, String> {
+    Ok(eval_strategy(&read_strategy(guide, Encoding::from(&puzzle))?))
 }
 
-fn main() -> IOResult<()> {
-    let args = aoc::args::Arguments::parse();
-    let file = File::open(args.file_name)?;
+#[derive(Debug, Args)]
+struct Options {
+    /// Interpretation of the second column, defaults to the one of the puzzle
+    #[arg(long, value_enum, conflicts_with = "both")]
+    strategy: Option<Encoding>,
 
-    println!("{}", eval_strategy(&read_strategy(&file,
-        &match args.puzzle {
-            Puzzle::P1 => read_round_1,
-            Puzzle::P2 => read_round_2,
+    /// Print the score of every round before the total
+    #[arg(long)]
+    breakdown: bool,
+}
+
+fn main() -> Result<(), String> {
+    let (args, options) = aoc::args::Arguments::parse_with::<Options>();
+    let guide = aoc::io::read_from(args.open()?).ok_or("Cannot read input")?;
+
+    for puzzle in args.puzzles() {
+        if options.breakdown {
+            let encoding = options.strategy.unwrap_or_else(|| Encoding::from(&puzzle));
+            for (i, score) in score_breakdown(&read_strategy(&guide, encoding)?).iter().enumerate() {
+                println!("Round {}: {}", i + 1, score);
+            }
         }
-    )));
+
+        println!("{}", match options.strategy {
+            Some(encoding) => eval_strategy(&read_strategy(&guide, encoding)?),
+            None => solve(&guide, puzzle)?,
+        });
+    }
 
     Ok(())
 }
@@ -139,6 +228,62 @@ mod tests {
         assert_eq!(eval_strategy(&example1()), 15);
     }
 
+    #[test]
+    fn breakdown() {
+        let scores = score_breakdown(&example1());
+
+        assert_eq!(scores, vec![8, 1, 6]);
+        assert_eq!(scores.iter().sum::<usize>(), eval_strategy(&example1()));
+    }
+
+    #[test]
+    fn encodings() {
+        let input = "A Y\nB X\nC Z\n";
+
+        let shapes = parse_strategy(&mut input.as_bytes().lines(), Encoding::Shapes);
+        assert_eq!(eval_strategy(&shapes.unwrap()), 15);
+
+        let outcomes = parse_strategy(&mut input.as_bytes().lines(), Encoding::Outcomes);
+        assert_eq!(eval_strategy(&outcomes.unwrap()), 12);
+
+        assert_eq!(solve(input, Puzzle::P1), Ok(15));
+        assert_eq!(solve(input, Puzzle::P2), Ok(12));
+    }
+
+    #[test]
+    fn too_many_tokens() {
+        let err = parse_strategy(&mut "A Y\nB X Z\n".as_bytes().lines(), Encoding::Shapes)
+            .expect_err("Second line has three codes");
+
+        assert!(err.starts_with("2: "), "{}", err);
+        assert!(err.contains("B X Z"), "{}", err);
+    }
+
+    #[test]
+    fn invalid_code() {
+        for encoding in [Encoding::Shapes, Encoding::Outcomes] {
+            let err = parse_strategy(&mut "A Y\nC Z\nA Q\n".as_bytes().lines(), encoding)
+                .expect_err("Third line has an invalid code");
+
+            assert!(err.starts_with("3: "), "{}", err);
+            assert!(err.ends_with(": Q"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn opponent_side() {
+        let strategy = example1();
+        let opponent = strategy.iter().map(opponent_score).sum::<usize>();
+        let shapes = strategy.iter().map(|r| r.0 as usize + r.1 as usize).sum::<usize>();
+
+        assert_eq!(opponent, 15);
+        assert_eq!(eval_strategy(&strategy) + opponent, shapes + 6 * strategy.len());
+
+        for (left, right) in strategy {
+            assert!(cmp_round(&left, &right).from_opponent() == cmp_round(&right, &left));
+        }
+    }
+
     #[test]
     fn p1_comparisons() {
         assert_eq!(eval_round(&(Shape::Rock, Shape::Paper)), 8);
@@ -153,6 +298,36 @@ mod tests {
         assert_eq!(find_match(&Shape::Scissors, &MatchResult::Win), Shape::Rock);
     }
 
+    fn verify_tables() -> bool {
+        let shapes = [Shape::Rock, Shape::Paper, Shape::Scissors];
+        let results = [MatchResult::Lose, MatchResult::Draw, MatchResult::Win];
+
+        shapes.iter().all(|opponent| results.iter().all(|expected| {
+            cmp_round(opponent, &find_match(opponent, expected)) == *expected
+        }))
+    }
+
+    #[test]
+    fn shape_cycle() {
+        for shape in [Shape::Rock, Shape::Paper, Shape::Scissors] {
+            assert_ne!(shape.beats(), shape);
+            assert_ne!(shape.loses_to(), shape);
+            assert_ne!(shape.beats(), shape.loses_to());
+
+            assert_eq!(shape.beats().loses_to(), shape);
+            assert_eq!(shape.loses_to().beats(), shape);
+            assert_eq!(shape.beats().beats().beats(), shape);
+        }
+
+        assert_eq!(Shape::Rock.beats(), Shape::Scissors);
+        assert_eq!(Shape::Rock.loses_to(), Shape::Paper);
+    }
+
+    #[test]
+    fn p2_tables() {
+        assert!(verify_tables());
+    }
+
     #[test]
     fn p2_comparisons() {
         assert_eq!(eval_round(&(Shape::Rock, Shape::Rock)), 4);
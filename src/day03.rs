@@ -1,17 +1,19 @@
 use aoc::args::Puzzle;
 use std::collections::HashSet as Set;
-use std::fs::File;
-use std::io::Result as IOResult;
-use std::io::{BufRead, BufReader, Error, ErrorKind};
+use std::io::BufRead;
 
 #[derive(Debug)]
 struct Rucksack {
-    parts: [Set<char>; 2],
+    parts: Vec<Set<char>>,
 }
 
 impl Rucksack {
-    fn _check_str(s: &str) -> Result<(), &'static str> {
-        if s.len() % 2 != 0 {
+    fn _check_str(s: &str, k: usize) -> Result<(), &'static str> {
+        if k == 0 {
+            return Err("Rucksack needs at least one compartment");
+        }
+
+        if !s.len().is_multiple_of(k) {
             return Err("Invalid length");
         }
 
@@ -25,22 +27,38 @@ impl Rucksack {
     }
 
     pub fn from_str(s: &str) -> Result<Rucksack, &'static str> {
-        Rucksack::_check_str(s)?;
+        Rucksack::from_str_parts(s, 2)
+    }
 
-        let mut rucksack: Rucksack = Rucksack {
-            parts: [Set::new(), Set::new()],
-        };
+    /* Splits the contents into ‹k› equally sized compartments. */
+    pub fn from_str_parts(s: &str, k: usize) -> Result<Rucksack, &'static str> {
+        Rucksack::_check_str(s, k)?;
 
-        let (l1, l2) = s.split_at(s.len() / 2);
-        for (part, split) in std::iter::zip(&mut rucksack.parts, [l1, l2]) {
-            part.extend(split.chars());
+        // An empty rucksack has ‹k› empty compartments, ‹chunks› would panic.
+        if s.is_empty() {
+            return Ok(Rucksack { parts: vec![Set::new(); k] });
         }
 
-        Ok(rucksack)
+        // The string is ASCII-only after the check, so byte chunks are chars.
+        let parts = s.as_bytes()
+            .chunks(s.len() / k)
+            .map(|chunk| chunk.iter().map(|b| char::from(*b)).collect())
+            .collect();
+
+        Ok(Rucksack { parts })
     }
 
+    /* Items present in every compartment. */
     pub fn common(&self) -> Set<char> {
-        self.parts[0].intersection(&self.parts[1]).copied().collect()
+        self.parts.iter()
+            .cloned()
+            .reduce(|a, p| a.intersection(&p).copied().collect())
+            .unwrap_or_default()
+    }
+
+    /* Items present in any compartment. */
+    pub fn items(&self) -> Set<char> {
+        self.parts.iter().flatten().copied().collect()
     }
 }
 
@@ -52,8 +70,8 @@ fn eval_letter(c: &char) -> u32 {
     }
 }
 
-fn read_rucksacks(file: &File) -> Result<Vec<Rucksack>, &'static str> {
-    let mut lines = BufReader::new(file).lines();
+fn read_rucksacks<R: BufRead>(input: R) -> Result<Vec<Rucksack>, &'static str> {
+    let mut lines = input.lines();
     let mut rucksacks = Vec::new();
 
     while let Some(line) = aoc::io::read_line(&mut lines) {
@@ -71,13 +89,20 @@ struct Group<'a> {
     rucksacks: Vec<&'a Rucksack>,
 }
 
-fn create_groups(rs: &Vec<Rucksack>) -> Result<Vec<Group>, &'static str> {
-    if rs.len() % 3 != 0 {
-        return Err("Invalid number of rucksacks");
+/* Number of elves sharing a badge in the puzzle. */
+const GROUP_SIZE: usize = 3;
+
+fn create_groups(rs: &[Rucksack], group_size: usize) -> Result<Vec<Group<'_>>, &'static str> {
+    if group_size == 0 {
+        return Err("Group size must be positive");
+    }
+
+    if !rs.len().is_multiple_of(group_size) {
+        return Err("Number of rucksacks is not a multiple of the group size");
     }
 
     let mut result = Vec::new();
-    for w in rs.chunks(3) {
+    for w in rs.chunks(group_size) {
         let group = Group {
             rucksacks: w.iter().collect(),
         };
@@ -88,15 +113,18 @@ fn create_groups(rs: &Vec<Rucksack>) -> Result<Vec<Group>, &'static str> {
     Ok(result)
 }
 
-fn find_badge(group: &Group) -> Result<char, &'static str> {
-    let common = group.rucksacks.iter()
-        .map(|r| r.parts[0].union(&r.parts[1])
-                .copied()
-                .collect::<Set<char>>())
+/* All items carried by every member of the group. */
+fn common_items(group: &Group) -> Set<char> {
+    group.rucksacks.iter()
+        .map(|r| r.items())
         .reduce(|a, m| a.intersection(&m)
                 .copied()
                 .collect())
-        .ok_or("BUG: No intersection found")?;
+        .unwrap_or_default()
+}
+
+fn find_badge(group: &Group) -> Result<char, &'static str> {
+    let common = common_items(group);
 
     if common.len() > 1 {
         return Err("Too many badges found");
@@ -105,25 +133,28 @@ fn find_badge(group: &Group) -> Result<char, &'static str> {
     common.iter().next().copied().ok_or("No badge found")
 }
 
-fn count_badges(rs: &Vec<Rucksack>) -> Result<u32, &'static str> {
-    let groups = create_groups(rs)?;
-    let badges = groups.iter()
-            .filter_map(|g| find_badge(g).ok())
-            .map(|p| eval_letter(&p));
+/* The badge of every group, in the order of the groups. */
+fn group_badges(rs: &[Rucksack], group_size: usize) -> Result<Vec<char>, &'static str> {
+    create_groups(rs, group_size)?.iter().map(find_badge).collect()
+}
 
-    Ok(badges.sum())
+fn count_badges(rs: &[Rucksack], group_size: usize) -> Result<u32, &'static str> {
+    Ok(group_badges(rs, group_size)?.iter().map(eval_letter).sum())
 }
 
-fn main() -> IOResult<()> {
-    let args = aoc::args::Arguments::parse();
-    let file = File::open(args.file_name)?;
+fn solve(rucksacks: &[Rucksack], puzzle: Puzzle) -> Result<u32, &'static str> {
+    match puzzle {
+        Puzzle::P1 => Ok(rucksacks_value(rucksacks)),
+        Puzzle::P2 => count_badges(rucksacks, GROUP_SIZE),
+    }
+}
 
-    let rucksacks = read_rucksacks(&file).expect("Cannot read rucksacks");
+fn main() -> Result<(), String> {
+    let args = aoc::args::Arguments::parse();
+    let rucksacks = read_rucksacks(args.open()?).expect("Cannot read rucksacks");
 
-    match args.puzzle {
-        Puzzle::P1 => println!("{}", rucksacks_value(&rucksacks)),
-        Puzzle::P2 => println!("{}", count_badges(&rucksacks)
-                               .map_err(|e| Error::new(ErrorKind::Other, e))?),
+    for puzzle in args.puzzles() {
+        println!("{}", solve(&rucksacks, puzzle)?);
     }
 
     Ok(())
@@ -152,7 +183,7 @@ mod tests {
     }
 
     fn rucksacks() -> Vec<Rucksack> {
-        vec![
+        [
             "vJrwpWtwJgWrhcsFMMfFFhFp",
             "jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL",
             "PmmdzqPrVvPwwTWBwg",
@@ -173,24 +204,73 @@ mod tests {
         assert_eq!(rucksacks[5].common(), letters("s"));
     }
 
+    #[test]
+    fn three_compartments() {
+        let rucksack = Rucksack::from_str_parts("abXbaYcbZ", 3).expect("Invalid rucksack");
+        assert_eq!(rucksack.parts.len(), 3);
+        assert_eq!(rucksack.common(), letters("b"));
+        assert_eq!(rucksack.items(), letters("abcXYZ"));
+    }
+
+    #[test]
+    fn indivisible_length() {
+        assert_eq!(Rucksack::from_str_parts("abcd", 3).unwrap_err(), "Invalid length");
+        assert_eq!(Rucksack::from_str("abc").unwrap_err(), "Invalid length");
+        assert!(Rucksack::from_str_parts("abcd", 0).is_err());
+
+        let empty = Rucksack::from_str("").expect("Empty rucksack is valid");
+        assert_eq!(empty.parts.len(), 2);
+        assert!(empty.common().is_empty());
+        assert_eq!(Rucksack::from_str_parts("", 3).expect("Empty rucksack is valid").parts.len(), 3);
+    }
+
     #[test]
     fn p1_total() {
         let rucksacks = rucksacks();
-        assert_eq!(rucksacks_value(&rucksacks), 157);
+        assert_eq!(solve(&rucksacks, Puzzle::P1), Ok(157));
     }
 
     #[test]
     fn p2_example1() {
         let rucksacks = rucksacks();
-        let groups = create_groups(&rucksacks).expect("Cannot create group");
+        let groups = create_groups(&rucksacks, GROUP_SIZE).expect("Cannot create group");
         assert_eq!(groups.len(), 2);
         assert_eq!(find_badge(&groups[0]).expect("find_badge()"), 'r');
         assert_eq!(find_badge(&groups[1]).expect("find_badge()"), 'Z');
     }
 
+    #[test]
+    fn common_items_all() {
+        let rucksacks = ["abXc", "YbaZ", "bqaz"].iter().map(|s| build(s)).collect::<Vec<_>>();
+        let groups = create_groups(&rucksacks, GROUP_SIZE).expect("Cannot create group");
+
+        assert_eq!(common_items(&groups[0]), letters("ab"));
+        assert!(find_badge(&groups[0]).is_err());
+    }
+
+    #[test]
+    fn badges() {
+        let rucksacks = rucksacks();
+        assert_eq!(group_badges(&rucksacks, GROUP_SIZE), Ok(vec!['r', 'Z']));
+        assert!(group_badges(&rucksacks, 2).is_err());
+    }
+
+    #[test]
+    fn pairs() {
+        let rucksacks = rucksacks();
+        let groups = create_groups(&rucksacks, 2).expect("Cannot create group");
+
+        assert_eq!(groups.len(), 3);
+        assert!(groups.iter().all(|g| g.rucksacks.len() == 2));
+        assert_eq!(common_items(&groups[2]), letters("GJZ"));
+
+        assert!(create_groups(&rucksacks, 4).is_err());
+        assert!(create_groups(&rucksacks, 0).is_err());
+    }
+
     #[test]
     fn p2_total() {
         let rucksacks = rucksacks();
-        assert_eq!(count_badges(&rucksacks).expect("count_badges()"), 70);
+        assert_eq!(solve(&rucksacks, Puzzle::P2), Ok(70));
     }
 }
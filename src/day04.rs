@@ -1,10 +1,8 @@
 use aoc::args::Puzzle;
-use std::fs::File;
-use std::io::Result as IOResult;
-use std::io::{BufRead, BufReader};
+use std::io::BufRead;
 use std::str::FromStr;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 struct Assign {
     start: u32,
     end: u32,
@@ -16,6 +14,11 @@ impl Assign {
         Assign { start, end }
     }
 
+    /* Number of sections in the assignment. */
+    pub fn size(&self) -> u32 {
+        self.end - self.start + 1
+    }
+
     pub fn contains_section(&self, num: u32) -> bool {
         self.start <= num && num <= self.end
     }
@@ -28,23 +31,94 @@ impl Assign {
         self.contains_section(other.start) || self.contains_section(other.end)
             || other.contains(self)
     }
+
+    /* Sections assigned to both, ‹None› if the assignments are disjoint. */
+    pub fn intersect(&self, other: &Self) -> Option<Assign> {
+        let start = self.start.max(other.start);
+        let end = self.end.min(other.end);
+
+        (start <= end).then_some(Assign { start, end })
+    }
+
+    /* Number of sections strictly between two disjoint assignments, ‹None›
+     * if there are none, i.e. if they overlap or are adjacent. */
+    #[allow(dead_code)]
+    pub fn gap(&self, other: &Self) -> Option<u32> {
+        let (left, right) = if self.end < other.start { (self, other) } else { (other, self) };
+
+        right.start.checked_sub(left.end + 1).filter(|n| *n > 0)
+    }
 }
 
+/* Reversed ranges such as ‹3-1› are rejected rather than normalized, since
+ * they most likely come from a mangled input. */
 impl FromStr for Assign {
-    type Err = &'static str;
+    type Err = String;
 
     fn from_str(value: &str) -> Result<Self, Self::Err> {
         let parts = value.split('-').collect::<Vec<&str>>();
 
         if parts.len() != 2 {
-            return Err("Invalid number of parts in range");
+            return Err(format!("Expected exactly one '-' in range: {}", value));
         }
 
-        Ok(Self {
-            start: parts[0].parse::<u32>().expect("Cannot parse range start"),
-            end: parts[1].parse::<u32>().expect("Cannot parse range end"),
+        let start = parts[0].trim().parse::<u32>()
+            .map_err(|_| format!("Invalid range start: {}", parts[0]))?;
+        let end = parts[1].trim().parse::<u32>()
+            .map_err(|_| format!("Invalid range end: {}", parts[1]))?;
+
+        if start > end {
+            return Err(format!("Range start is after its end: {}", value));
+        }
+
+        Ok(Self { start, end })
+    }
+}
+
+#[derive(Debug)]
+struct AssignGroup(Vec<Assign>);
+
+impl AssignGroup {
+    fn _any_pair<F>(elves: &[Assign], pred: F) -> bool
+            where F: Fn(&Assign, &Assign) -> bool {
+        elves.iter().enumerate().any(|(i, a)| {
+            elves.iter().skip(i + 1).any(|b| pred(a, b))
         })
     }
+
+    fn any_contained(elves: &[Assign]) -> bool {
+        Self::_any_pair(elves, |a, b| a.contains(b) || b.contains(a))
+    }
+
+    fn any_overlap(elves: &[Assign]) -> bool {
+        Self::_any_pair(elves, Assign::overlaps)
+    }
+
+    #[allow(dead_code)]
+    pub fn has_any_contained(&self) -> bool {
+        Self::any_contained(&self.0)
+    }
+
+    #[allow(dead_code)]
+    pub fn has_any_overlap(&self) -> bool {
+        Self::any_overlap(&self.0)
+    }
+}
+
+impl FromStr for AssignGroup {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let elves = value.split(',')
+            .map(|p| p.parse::<Assign>())
+            .collect::<Result<Vec<Assign>, _>>()?;
+
+        if elves.len() < 2 {
+            return Err(format!("Too few parts in assignment group: {}", value));
+        }
+
+        Ok(Self(elves))
+    }
 }
 
 #[derive(Debug)]
@@ -54,30 +128,38 @@ struct AssignPair {
 
 impl AssignPair {
     fn has_complete_overlap(&self) -> bool {
-        self.elves[0].contains(&self.elves[1])
-            || self.elves[1].contains(&self.elves[0])
+        AssignGroup::any_contained(&self.elves)
     }
 
     fn has_overlap(&self) -> bool {
-        self.elves[0].overlaps(&self.elves[1])
+        self.elves[0].intersect(&self.elves[1]).is_some()
+    }
+
+    /* Number of sections assigned to both elves. */
+    #[allow(dead_code)]
+    fn intersection_size(&self) -> u32 {
+        self.elves[0].intersect(&self.elves[1]).map_or(0, |a| a.size())
+    }
+
+    /* Number of sections assigned to at least one of the elves. */
+    #[allow(dead_code)]
+    fn union_size(&self) -> u32 {
+        self.elves[0].size() + self.elves[1].size() - self.intersection_size()
     }
 }
 
 impl FromStr for AssignPair {
-    type Err = &'static str;
+    type Err = String;
 
     fn from_str(value: &str) -> Result<Self, Self::Err> {
         let parts = value.split(',').collect::<Vec<&str>>();
 
         if parts.len() != 2 {
-            return Err("Invalid number of parts in assignment pair");
+            return Err(format!("Expected exactly one ',' in assignment pair: {}", value));
         }
 
         Ok(Self {
-            elves: parts.iter()
-                .map(|p| p.parse::<Assign>().expect("Cannot parse range"))
-                .collect::<Vec<Assign>>()
-                .try_into().expect("Invalid number of ranges"),
+            elves: [parts[0].parse()?, parts[1].parse()?],
         })
     }
 }
@@ -90,26 +172,23 @@ fn count_overlaps(pairs: &[AssignPair]) -> usize {
     pairs.iter().filter(|p| p.has_overlap()).count()
 }
 
-fn read_pairs(file: &File) -> Result<Vec<AssignPair>, &'static str> {
-    let mut lines = BufReader::new(file).lines();
-    let mut pairs = Vec::new();
+fn read_pairs<R: BufRead>(input: R) -> Result<Vec<AssignPair>, String> {
+    aoc::io::parse_lines_from(input)
+}
 
-    while let Some(line) = aoc::io::read_line(&mut lines) {
-        pairs.push(line.parse()?);
+fn solve(pairs: &[AssignPair], puzzle: Puzzle) -> usize {
+    match puzzle {
+        Puzzle::P1 => count_complete_overlaps(pairs),
+        Puzzle::P2 => count_overlaps(pairs),
     }
-
-    Ok(pairs)
 }
 
-fn main() -> IOResult<()> {
+fn main() -> Result<(), String> {
     let args = aoc::args::Arguments::parse();
-    let file = File::open(args.file_name)?;
+    let pairs = read_pairs(args.open()?)?;
 
-    let pairs = read_pairs(&file).expect("Cannot read rucksacks");
-
-    match args.puzzle {
-        Puzzle::P1 => println!("{}", count_complete_overlaps(&pairs)),
-        Puzzle::P2 => println!("{}", count_overlaps(&pairs)),
+    for puzzle in args.puzzles() {
+        println!("{}", solve(&pairs, puzzle));
     }
 
     Ok(())
@@ -125,6 +204,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn gap() {
+        assert_eq!(Assign::new(2, 4).gap(&Assign::new(7, 9)), Some(2));
+        assert_eq!(Assign::new(7, 9).gap(&Assign::new(2, 4)), Some(2));
+
+        assert_eq!(Assign::new(2, 4).gap(&Assign::new(5, 9)), None);
+        assert_eq!(Assign::new(2, 4).gap(&Assign::new(4, 9)), None);
+        assert_eq!(Assign::new(2, 8).gap(&Assign::new(3, 4)), None);
+    }
+
     #[test]
     fn contains() {
         assert!(Assign::new(1, 4).contains(&Assign::new(1, 4)));
@@ -162,7 +251,7 @@ mod tests {
 
     #[test]
     fn p1_example() {
-        assert_eq!(count_complete_overlaps(&pairs()), 2);
+        assert_eq!(solve(&pairs(), Puzzle::P1), 2);
     }
 
     #[test]
@@ -177,8 +266,75 @@ mod tests {
         assert!(!pairs[3].has_overlap());
     }
 
+    #[test]
+    fn groups() {
+        let group = AssignGroup(vec![
+            Assign::new(1, 3),
+            Assign::new(5, 9),
+            Assign::new(6, 7),
+        ]);
+
+        assert!(group.has_any_contained());
+        assert!(group.has_any_overlap());
+
+        let group = "1-2,4-5,7-8".parse::<AssignGroup>().expect("Cannot parse group");
+        assert!(!group.has_any_contained());
+        assert!(!group.has_any_overlap());
+
+        let group = "1-4,7-8,4-5".parse::<AssignGroup>().expect("Cannot parse group");
+        assert!(!group.has_any_contained());
+        assert!(group.has_any_overlap());
+
+        assert!("1-4".parse::<AssignGroup>().is_err());
+    }
+
+    #[test]
+    fn intersect() {
+        assert_eq!(Assign::new(2, 4).intersect(&Assign::new(5, 7)), None);
+        assert!(!pair(2, 4, 5, 7).has_overlap());
+
+        assert_eq!(Assign::new(2, 6).intersect(&Assign::new(4, 8)), Some(Assign::new(4, 6)));
+        assert_eq!(Assign::new(4, 8).intersect(&Assign::new(2, 6)), Some(Assign::new(4, 6)));
+        assert_eq!(Assign::new(2, 8).intersect(&Assign::new(3, 7)), Some(Assign::new(3, 7)));
+        assert_eq!(Assign::new(5, 7).intersect(&Assign::new(7, 9)), Some(Assign::new(7, 7)));
+    }
+
+    #[test]
+    fn section_counts() {
+        assert_eq!(pair(2, 8, 3, 7).intersection_size(), 5);
+        assert_eq!(pair(2, 8, 3, 7).union_size(), 7);
+
+        assert_eq!(pair(5, 7, 7, 9).intersection_size(), 1);
+        assert_eq!(pair(5, 7, 7, 9).union_size(), 5);
+
+        assert_eq!(pair(2, 4, 6, 8).intersection_size(), 0);
+        assert_eq!(pair(2, 4, 6, 8).union_size(), 6);
+
+        for p in pairs() {
+            assert_eq!(p.has_overlap(), p.intersection_size() > 0);
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let p = "2-4,6-8".parse::<AssignPair>().expect("Valid pair");
+        assert_eq!(p.elves, [Assign::new(2, 4), Assign::new(6, 8)]);
+
+        let err = "3-1,2-4".parse::<AssignPair>().expect_err("Reversed range");
+        assert!(err.contains("3-1"), "{}", err);
+
+        let err = "1-2-3,4-5".parse::<AssignPair>().expect_err("Too many dashes");
+        assert!(err.contains("1-2-3"), "{}", err);
+
+        let err = "1-x,4-5".parse::<AssignPair>().expect_err("Bad integer");
+        assert!(err.ends_with(": x"), "{}", err);
+
+        assert!("1-2".parse::<AssignPair>().is_err());
+        assert!("1-2,3-4,5-6".parse::<AssignPair>().is_err());
+    }
+
     #[test]
     fn p2_example() {
-        assert_eq!(count_overlaps(&pairs()), 4);
+        assert_eq!(solve(&pairs(), Puzzle::P2), 4);
     }
 }
@@ -2,9 +2,8 @@ use aoc::args::Puzzle;
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::collections::VecDeque;
-use std::fs::File;
-use std::io::Result as IOResult;
-use std::io::{BufRead, BufReader, Lines};
+use std::fmt;
+use std::io::{BufRead, Lines};
 use std::str::FromStr;
 use std::cell::RefCell;
 
@@ -18,7 +17,7 @@ struct Ship {
 impl Ship {
     pub fn top(&self) -> Result<Vec<char>, &'static str> {
         let chars = self.crates.iter()
-            .map(|vec| vec.borrow().last().ok_or("Cargo stack is empty").map(|c| *c));
+            .map(|vec| vec.borrow().last().ok_or("Cargo stack is empty").copied());
 
         chars.collect()
     }
@@ -28,7 +27,7 @@ impl Ship {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 struct Instruction {
     src: usize,
     dst: usize,
@@ -44,6 +43,15 @@ impl Instruction {
     fn new(src: usize, dst: usize, count: u32) -> Instruction {
         Instruction { src, dst, count }
     }
+
+    /* Checks that both stacks exist on a ship with ‹stacks› stacks. */
+    fn check(&self, stacks: usize) -> Result<(), &'static str> {
+        if self.src >= stacks || self.dst >= stacks {
+            return Err("stack index out of range");
+        }
+
+        Ok(())
+    }
 }
 
 impl FromStr for Instruction {
@@ -64,6 +72,12 @@ impl FromStr for Instruction {
     }
 }
 
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.count, self.src + 1, self.dst + 1)
+    }
+}
+
 #[derive(Clone)]
 struct RearrProc {
     ship: Ship,
@@ -73,6 +87,11 @@ struct RearrProc {
 trait CrateMover {
     fn exec(stacks: &mut Vec<RefCell<Stack>>, instr: &Instruction)
         -> Result<(), &'static str>;
+
+    /* The crates that ‹exec› moves for ‹instr›, in the order it puts them
+     * down, given the top ‹instr.count› crates of the source stack listed
+     * from the bottom up. */
+    fn moved(instr: &Instruction, lifted: Vec<char>) -> Vec<char>;
 }
 
 struct CrateMover9000;
@@ -92,6 +111,14 @@ impl CrateMover for CrateMover9000 {
 
         Ok(())
     }
+
+    fn moved(instr: &Instruction, lifted: Vec<char>) -> Vec<char> {
+        if instr.src == instr.dst {
+            return Vec::new();
+        }
+
+        lifted.into_iter().rev().collect()
+    }
 }
 
 struct CrateMover9001;
@@ -103,7 +130,8 @@ impl CrateMover for CrateMover9001 {
             return Ok(());
         }
 
-        // Condition ‹instr.src ≠ instr.dst› is guaranteed above.
+        // Condition ‹instr.src ≠ instr.dst› is guaranteed above, otherwise
+        // the second ‹borrow_mut› would panic.
         let mut src = stacks[instr.src].borrow_mut();
         let mut dst = stacks[instr.dst].borrow_mut();
 
@@ -114,11 +142,87 @@ impl CrateMover for CrateMover9001 {
 
         Ok(())
     }
+
+    fn moved(instr: &Instruction, lifted: Vec<char>) -> Vec<char> {
+        if instr.src == instr.dst {
+            return Vec::new();
+        }
+
+        lifted
+    }
+}
+
+/* Like ‹CrateMover9000›, but a move within a single stack actually takes
+ * the crates off and puts them back, reversing the top ‹count› crates. */
+#[allow(dead_code)]
+struct CrateMover9002;
+
+impl CrateMover for CrateMover9002 {
+    fn exec(stacks: &mut Vec<RefCell<Stack>>, instr: &Instruction)
+            -> Result<(), &'static str> {
+        // Only one stack is borrowed at a time, so ‹src = dst› is fine.
+        let held = {
+            let mut src = stacks[instr.src].borrow_mut();
+            let count = instr.count as usize;
+
+            if count > src.len() {
+                return Err("Source stack is empty");
+            }
+
+            let skip = src.len() - count;
+            src.drain(skip ..).rev().collect::<Vec<char>>()
+        };
+
+        stacks[instr.dst].borrow_mut().extend(held);
+        Ok(())
+    }
+
+    fn moved(_instr: &Instruction, lifted: Vec<char>) -> Vec<char> {
+        lifted.into_iter().rev().collect()
+    }
 }
 
 impl RearrProc {
+    #[allow(dead_code)]
+    pub fn plan_to_string(&self) -> String {
+        self.plan.iter().map(Instruction::to_string).collect::<Vec<_>>().join("\n")
+    }
+
+    /* Dry run of the plan on a copy of the ship: for each instruction, the
+     * (0-based) source and destination and the crates that move, in the
+     * order ‹CM› puts them down. Stops at the first instruction that cannot
+     * be executed. */
+    #[allow(dead_code)]
+    pub fn plan_moves<CM: CrateMover>(&self) -> Vec<(usize, usize, Vec<char>)> {
+        let mut stacks = self.ship.crates.clone();
+        let mut moves = Vec::new();
+
+        for instr in &self.plan {
+            if instr.check(stacks.len()).is_err() {
+                break;
+            }
+
+            let lifted = {
+                let src = stacks[instr.src].borrow();
+                match src.len().checked_sub(instr.count as usize) {
+                    Some(skip) => src[skip ..].to_vec(),
+                    None => break,
+                }
+            };
+
+            if CM::exec(&mut stacks, instr).is_err() {
+                break;
+            }
+
+            moves.push((instr.src, instr.dst, CM::moved(instr, lifted)));
+        }
+
+        moves
+    }
+
     pub fn run<CM: CrateMover>(&mut self) -> Result<(), &'static str> {
         while let Some(instr) = self.plan.pop_front() {
+            instr.check(self.ship.crates.len())?;
             CM::exec(&mut self.ship.crates, &instr)?;
         }
 
@@ -126,6 +230,18 @@ impl RearrProc {
     }
 }
 
+/* Runs the procedure on a fresh copy of the ship for each crane model and
+ * returns the top crates for ‹CrateMover9000› and ‹CrateMover9001›. */
+fn solve(procedure: &RearrProc) -> Result<(String, String), &'static str> {
+    fn run_on_copy<CM: CrateMover>(procedure: &RearrProc) -> Result<String, &'static str> {
+        let mut copy = procedure.clone();
+        copy.run::<CM>()?;
+        copy.ship.top_str()
+    }
+
+    Ok((run_on_copy::<CrateMover9000>(procedure)?, run_on_copy::<CrateMover9001>(procedure)?))
+}
+
 enum ShipPart {
     End,
     Part(Vec<Option<char>>),
@@ -138,9 +254,23 @@ fn read_ship_part(line: &str) -> Result<ShipPart, &'static str> {
         return Ok(ShipPart::End);
     }
 
+    // The numbering row; labels may span several columns once there are
+    // more than nine stacks, so read them as whole words.
+    if line.chars().all(|c| c == ' ' || c.is_ascii_digit()) {
+        let labels = line.split_whitespace()
+            .map(|label| label.parse::<usize>().map_err(|_| "Invalid stack label"))
+            .collect::<Result<Vec<usize>, _>>()?;
+
+        if labels.iter().enumerate().any(|(i, label)| *label != i + 1) {
+            return Err("Mismatched row numbers");
+        }
+
+        return Ok(ShipPart::Check(labels.len()));
+    }
+
     // The length of a string with ⟦n⟧ crates is ⟦3n + (n - 1) = 4n - 1⟧,
     // thus we only need to test if ⟦n + 1⟧ is divisible by four.
-    if (line.len() + 1) % 4 != 0 {
+    if !(line.len() + 1).is_multiple_of(4) {
         return Err("Invalid line length");
     }
 
@@ -153,20 +283,6 @@ fn read_ship_part(line: &str) -> Result<ShipPart, &'static str> {
         crates.push(if c == ' ' { None } else { Some(c) });
     }
 
-    if crates.iter().all(|w| w.map_or(false, |c| c.is_ascii_digit())) {
-        // Check that number line up.
-        for (i, crte) in crates.iter().enumerate() {
-            let index = crte.ok_or("BUG: Too few indices in check row")?
-                    .to_digit(10).ok_or("BUG: Not a digit")?;
-
-            if index as usize != i + 1 {
-                return Err("Mismatched row numbers");
-            }
-        }
-
-        return Ok(ShipPart::Check(crates_count));
-    }
-
     Ok(ShipPart::Part(crates))
 }
 
@@ -174,8 +290,8 @@ fn read_ship<T>(lines: &mut Lines<T>) -> Result<Ship, &'static str>
         where T: BufRead {
     let mut parts: Vec<VecDeque<char>> = Vec::new();
 
-    while let Some(line) = aoc::io::read_line(lines) {
-        match read_ship_part(&line)? {
+    while let Some(line) = aoc::io::try_read_line(lines) {
+        match read_ship_part(&line.map_err(|_| "Cannot read line")?)? {
             ShipPart::End => break,
             ShipPart::Part(part) => {
                 if parts.len() < part.len() {
@@ -208,33 +324,32 @@ fn read_instructions<T>(lines: &mut Lines<T>) -> Result<Vec<Instruction>, &'stat
         where T: BufRead {
     let mut instr: Vec<Instruction> = Vec::new();
 
-    while let Some(line) = aoc::io::read_line(lines) {
-        instr.push(line.parse()?);
+    while let Some(line) = aoc::io::try_read_line(lines) {
+        instr.push(line.map_err(|_| "Cannot read line")?.parse()?);
     }
 
     Ok(instr)
 }
 
-fn read_procedure(file: &File) -> Result<RearrProc, &'static str> {
-    let mut lines = BufReader::new(file).lines();
+fn read_procedure<R: BufRead>(input: R) -> Result<RearrProc, &'static str> {
+    let mut lines = input.lines();
     let ship = read_ship(&mut lines)?;
     let plan = read_instructions(&mut lines)?.into();
 
     Ok(RearrProc { ship, plan })
 }
 
-fn main() -> IOResult<()> {
+fn main() -> Result<(), String> {
     let args = aoc::args::Arguments::parse();
-    let file = File::open(args.file_name)?;
-
-    let mut procedure = read_procedure(&file).expect("Cannot read procedure");
-
-    match args.puzzle {
-        Puzzle::P1 => procedure.run::<CrateMover9000>(),
-        Puzzle::P2 => procedure.run::<CrateMover9001>(),
-    }.expect("Failed to run the procedure");
-
-    println!("{}", procedure.ship.top_str().expect("Cannot get top string"));
+    let procedure = read_procedure(args.open()?).expect("Cannot read procedure");
+    let (p1, p2) = solve(&procedure).expect("Failed to run the procedure");
+
+    for puzzle in args.puzzles() {
+        println!("{}", match puzzle {
+            Puzzle::P1 => &p1,
+            Puzzle::P2 => &p2,
+        });
+    }
 
     Ok(())
 }
@@ -291,6 +406,146 @@ mod tests {
         assert_eq!(rp.ship.top_str().expect("Cannot get top row"), "CMZ");
     }
 
+    #[test]
+    fn plan_round_trip() {
+        let rp = RearrProc {
+            ship: example_ship(),
+            plan: [
+                Instruction::new(1, 0, 1),
+                Instruction::new(0, 2, 3),
+                Instruction::new(1, 0, 2),
+                Instruction::new(0, 1, 1),
+            ].into(),
+        };
+
+        let text = rp.plan_to_string();
+        assert_eq!(text.lines().next(), Some("move 1 from 2 to 1"));
+
+        let parsed = text.lines()
+            .map(|l| l.parse::<Instruction>().expect("Cannot parse instruction"))
+            .collect::<Vec<_>>();
+        assert_eq!(parsed, Vec::from(rp.plan));
+    }
+
+    #[test]
+    fn solve_both() {
+        let rp = RearrProc {
+            ship: example_ship(),
+            plan: [
+                Instruction::new(1, 0, 1),
+                Instruction::new(0, 2, 3),
+                Instruction::new(1, 0, 2),
+                Instruction::new(0, 1, 1),
+            ].into(),
+        };
+
+        assert_eq!(solve(&rp), Ok((String::from("CMZ"), String::from("MCD"))));
+        assert_eq!(rp.plan.len(), 4);
+        assert_eq!(rp.ship.crates, example_ship().crates);
+    }
+
+    #[test]
+    fn dry_run() {
+        let rp = RearrProc {
+            ship: example_ship(),
+            plan: [Instruction::new(1, 2, 3), Instruction::new(0, 1, 1)].into(),
+        };
+
+        assert_eq!(rp.plan_moves::<CrateMover9000>(),
+                   vec![(1, 2, vec!['D', 'C', 'M']), (0, 1, vec!['N'])]);
+        assert_eq!(rp.plan_moves::<CrateMover9001>(),
+                   vec![(1, 2, vec!['M', 'C', 'D']), (0, 1, vec!['N'])]);
+
+        assert_eq!(rp.ship.crates, example_ship().crates);
+        assert_eq!(rp.plan.len(), 2);
+    }
+
+    #[test]
+    fn self_move() {
+        let self_move = || RearrProc {
+            ship: example_ship(),
+            plan: [Instruction::new(1, 1, 2)].into(),
+        };
+
+        let mut rp = self_move();
+        rp.run::<CrateMover9000>().expect("Plan failed");
+        assert_eq!(rp.ship.crates, example_ship().crates);
+
+        let mut rp = self_move();
+        rp.run::<CrateMover9001>().expect("Plan failed");
+        assert_eq!(rp.ship.crates, example_ship().crates);
+
+        let mut rp = self_move();
+        assert_eq!(rp.plan_moves::<CrateMover9000>(), vec![(1, 1, vec![])]);
+        assert_eq!(rp.plan_moves::<CrateMover9001>(), vec![(1, 1, vec![])]);
+        assert_eq!(rp.plan_moves::<CrateMover9002>(), vec![(1, 1, vec!['D', 'C'])]);
+
+        rp.run::<CrateMover9002>().expect("Plan failed");
+        assert_eq!(rp.ship.crates[1], stack_from_str("MDC"));
+        assert_eq!(rp.ship.top_str(), Ok(String::from("NCP")));
+
+        let mut rp = RearrProc {
+            ship: example_ship(),
+            plan: [
+                Instruction::new(1, 0, 1),
+                Instruction::new(0, 2, 3),
+                Instruction::new(1, 0, 2),
+                Instruction::new(0, 1, 1),
+            ].into(),
+        };
+
+        rp.run::<CrateMover9002>().expect("Plan failed");
+        assert_eq!(rp.ship.top_str(), Ok(String::from("CMZ")));
+
+        // Moves that leave the stack as it was still move crates.
+        let rp = RearrProc {
+            ship: example_ship(),
+            plan: [Instruction::new(1, 1, 1)].into(),
+        };
+        assert_eq!(rp.plan_moves::<CrateMover9002>(), vec![(1, 1, vec!['D'])]);
+
+        let rp = RearrProc {
+            ship: Ship { crates: vec![stack_from_str("AA")] },
+            plan: [Instruction::new(0, 0, 2)].into(),
+        };
+        assert_eq!(rp.plan_moves::<CrateMover9002>(), vec![(0, 0, vec!['A', 'A'])]);
+    }
+
+    #[test]
+    fn missing_stack() {
+        let instr = "move 1 from 3 to 1".parse::<Instruction>().expect("Valid instruction");
+        let ship = Ship {
+            crates: vec![stack_from_str("AB"), stack_from_str("C")],
+        };
+
+        let mut rp = RearrProc { ship: ship.clone(), plan: [instr.clone()].into() };
+        assert_eq!(rp.run::<CrateMover9000>(), Err("stack index out of range"));
+
+        let mut rp = RearrProc { ship: ship.clone(), plan: [instr.clone()].into() };
+        assert_eq!(rp.run::<CrateMover9001>(), Err("stack index out of range"));
+
+        let rp = RearrProc { ship, plan: [Instruction::new(0, 2, 1)].into() };
+        assert!(rp.plan_moves::<CrateMover9002>().is_empty());
+        assert_eq!(solve(&rp), Err("stack index out of range"));
+    }
+
+    #[test]
+    fn ten_stacks() {
+        let input = concat!(
+            "                                    [K]\n",
+            "[A] [B] [C] [D] [E] [F] [G] [H] [I] [J]\n",
+            " 1   2   3   4   5   6   7   8   9  10 \n",
+            "\n",
+        );
+
+        let ship = read_ship(&mut input.as_bytes().lines()).expect("Cannot read ship");
+        assert_eq!(ship.crates.len(), 10);
+        assert_eq!(ship.top_str(), Ok(String::from("ABCDEFGHIK")));
+
+        let input = "[A] [B]\n 1   3 \n\n";
+        assert!(read_ship(&mut input.as_bytes().lines()).is_err());
+    }
+
     #[test]
     fn p2_complete_plan() {
         let mut rp = RearrProc {
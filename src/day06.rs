@@ -1,8 +1,6 @@
 use aoc::args::Puzzle;
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::io::Result as IOResult;
+use std::collections::HashMap;
+use std::io::BufRead;
 use std::str::FromStr;
 
 struct Signal(Vec<char>);
@@ -17,34 +15,74 @@ impl FromStr for Signal {
 
 impl Signal {
     fn start(&self, ws: usize) -> Option<usize> {
-        for (index, chunk) in self.0.windows(ws).enumerate() {
-            let set: HashSet<&char> = HashSet::from_iter(chunk);
-            if set.len() == ws {
-                return Some(ws + index);
+        self.start_detail(ws).map(|(end, _)| end)
+    }
+
+    /* Position after the first marker of ‹ws› distinct chars and the marker. */
+    fn start_detail(&self, ws: usize) -> Option<(usize, String)> {
+        self.markers(ws).next().map(|(end, chunk)| (end, chunk.iter().collect()))
+    }
+
+    /* Positions after every window of ‹ws› distinct chars, in order. */
+    #[allow(dead_code)]
+    fn all_markers(&self, ws: usize) -> Vec<usize> {
+        self.markers(ws).map(|(end, _)| end).collect()
+    }
+
+    /* Slides the window one char at a time, keeping the count of every char
+     * in it and the number of chars that occur more than once. */
+    fn markers(&self, ws: usize) -> impl Iterator<Item = (usize, &[char])> {
+        let mut counts: HashMap<char, usize> = HashMap::new();
+        let mut duplicates: usize = 0;
+
+        self.0.iter().enumerate().filter_map(move |(i, c)| {
+            let count = counts.entry(*c).or_default();
+            *count += 1;
+            if *count == 2 {
+                duplicates += 1;
+            }
+
+            if i >= ws {
+                let count = counts.get_mut(&self.0[i - ws]).expect("BUG: Char not counted");
+                *count -= 1;
+                if *count == 1 {
+                    duplicates -= 1;
+                }
             }
-        }
 
-        None
+            let end = i + 1;
+            (end >= ws && duplicates == 0).then(|| (end, &self.0[end - ws .. end]))
+        })
     }
 }
 
+/* Every non-empty line of the input is a separate datastream. */
+fn read_signals<R: BufRead>(input: R) -> Result<Vec<Signal>, &'static str> {
+    let signals = aoc::io::parse_lines_from::<Signal, _>(input)?;
 
-fn read_signal(file: &File) -> Result<Signal, &'static str> {
-    let mut lines = BufReader::new(file).lines();
+    if signals.is_empty() {
+        return Err("No line");
+    }
 
-    let line = aoc::io::read_line(&mut lines).ok_or("No line")?;
-    line.parse()
+    Ok(signals)
 }
 
-fn main() -> IOResult<()> {
-    let args = aoc::args::Arguments::parse();
-    let file = File::open(args.file_name)?;
+/* Position after the start-of-packet or start-of-message marker. */
+fn solve(signal: &Signal, puzzle: Puzzle) -> Option<usize> {
+    match puzzle {
+        Puzzle::P1 => signal.start(4),
+        Puzzle::P2 => signal.start(14),
+    }
+}
 
-    let signal = read_signal(&file).expect("Cannot read signal line");
+fn main() -> Result<(), String> {
+    let args = aoc::args::Arguments::parse();
+    let signals = read_signals(args.open()?).expect("Cannot read signal lines");
 
-    match args.puzzle {
-        Puzzle::P1 => println!("{}", signal.start(4).expect("No signal start found")),
-        Puzzle::P2 => println!("{}", signal.start(14).expect("No message start found")),
+    for signal in signals {
+        for puzzle in args.puzzles() {
+            println!("{}", solve(&signal, puzzle).expect("No marker found"));
+        }
     }
 
     Ok(())
@@ -60,19 +98,77 @@ mod tests {
 
     #[test]
     fn p1_examples() {
-        assert_eq!(sig("mjqjpqmgbljsphdztnvjfqwrcgsmlb").start(4), Some(7));
-        assert_eq!(sig("bvwbjplbgvbhsrlpgdmjqwftvncz").start(4), Some(5));
-        assert_eq!(sig("nppdvjthqldpwncqszvftbrmjlhg").start(4), Some(6));
-        assert_eq!(sig("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg").start(4), Some(10));
-        assert_eq!(sig("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw").start(4), Some(11));
+        assert_eq!(solve(&sig("mjqjpqmgbljsphdztnvjfqwrcgsmlb"), Puzzle::P1), Some(7));
+        assert_eq!(solve(&sig("bvwbjplbgvbhsrlpgdmjqwftvncz"), Puzzle::P1), Some(5));
+        assert_eq!(solve(&sig("nppdvjthqldpwncqszvftbrmjlhg"), Puzzle::P1), Some(6));
+        assert_eq!(solve(&sig("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"), Puzzle::P1), Some(10));
+        assert_eq!(solve(&sig("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"), Puzzle::P1), Some(11));
+    }
+
+    #[test]
+    fn marker_detail() {
+        assert_eq!(sig("bvwbjplbgvbhsrlpgdmjqwftvncz").start_detail(4),
+                   Some((5, String::from("vwbj"))));
+        assert_eq!(sig("aaaa").start_detail(2), None);
+    }
+
+    /* The straightforward scan that builds a set for every window. */
+    fn naive_markers(signal: &Signal, ws: usize) -> Vec<usize> {
+        signal.0.windows(ws).enumerate()
+            .filter(|(_, chunk)| std::collections::HashSet::<&char>::from_iter(*chunk).len() == ws)
+            .map(|(index, _)| ws + index)
+            .collect()
+    }
+
+    #[test]
+    fn sliding_matches_naive() {
+        for s in [
+            "mjqjpqmgbljsphdztnvjfqwrcgsmlb",
+            "bvwbjplbgvbhsrlpgdmjqwftvncz",
+            "nppdvjthqldpwncqszvftbrmjlhg",
+            "nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg",
+            "zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw",
+        ] {
+            let signal = sig(s);
+            for ws in [1, 4, 14] {
+                assert_eq!(signal.all_markers(ws), naive_markers(&signal, ws), "{} {}", s, ws);
+            }
+        }
+    }
+
+    #[test]
+    fn all_markers() {
+        let signal = sig("bvwbjplbgvbhsrlpgdmjqwftvncz");
+        let markers = signal.all_markers(4);
+
+        assert_eq!(markers.first().copied(), signal.start(4));
+        assert_eq!(markers[.. 3], [5, 6, 7]);
+        assert!(markers.windows(2).all(|w| w[0] < w[1]));
+
+        assert!(sig("abab").all_markers(3).is_empty());
+        assert_eq!(sig("abca").all_markers(3), vec![3, 4]);
+    }
+
+    #[test]
+    fn several_lines() {
+        let input = "mjqjpqmgbljsphdztnvjfqwrcgsmlb\n\nbvwbjplbgvbhsrlpgdmjqwftvncz\n";
+        let signals = read_signals(input.as_bytes()).expect("Cannot read signals");
+
+        assert_eq!(signals.iter().map(|s| s.start(4)).collect::<Vec<_>>(), vec![Some(7), Some(5)]);
+
+        let single = read_signals("nppdvjthqldpwncqszvftbrmjlhg".as_bytes()).expect("One line");
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].start(4), Some(6));
+
+        assert!(read_signals("".as_bytes()).is_err());
     }
 
     #[test]
     fn p2_examples() {
-        assert_eq!(sig("mjqjpqmgbljsphdztnvjfqwrcgsmlb").start(14), Some(19));
-        assert_eq!(sig("bvwbjplbgvbhsrlpgdmjqwftvncz").start(14), Some(23));
-        assert_eq!(sig("nppdvjthqldpwncqszvftbrmjlhg").start(14), Some(23));
-        assert_eq!(sig("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg").start(14), Some(29));
-        assert_eq!(sig("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw").start(14), Some(26));
+        assert_eq!(solve(&sig("mjqjpqmgbljsphdztnvjfqwrcgsmlb"), Puzzle::P2), Some(19));
+        assert_eq!(solve(&sig("bvwbjplbgvbhsrlpgdmjqwftvncz"), Puzzle::P2), Some(23));
+        assert_eq!(solve(&sig("nppdvjthqldpwncqszvftbrmjlhg"), Puzzle::P2), Some(23));
+        assert_eq!(solve(&sig("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"), Puzzle::P2), Some(29));
+        assert_eq!(solve(&sig("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"), Puzzle::P2), Some(26));
     }
 }
@@ -1,12 +1,11 @@
 use aoc::args::Puzzle;
+use clap::Args;
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::collections::btree_map::Entry;
 use std::collections::BTreeMap;
 use std::fmt;
-use std::fs::File;
-use std::io::Result as IOResult;
-use std::io::{BufRead, BufReader};
+use std::io::BufRead;
 use std::rc::Rc;
 use std::str::FromStr;
 
@@ -21,6 +20,26 @@ pub struct Directory {
     entries: BTreeMap<String, INode>,
 }
 
+impl Directory {
+    /* Removes the file or the whole directory at ‹path›, relative to this
+     * directory, and returns it. */
+    #[allow(dead_code)]
+    fn remove(&mut self, path: &Path) -> Result<INode, &'static str> {
+        let (name, parents) = path.dirs.split_last().ok_or("Cannot remove root")?;
+        let mut dir = self;
+
+        for comp in parents {
+            dir = match dir.entries.get_mut(comp) {
+                Some(INode::Directory(ndir)) => ndir,
+                Some(INode::File(_)) => return Err("Not a directory"),
+                None => return Err("No such file or directory"),
+            }
+        }
+
+        dir.entries.remove(name).ok_or("No such file or directory")
+    }
+}
+
 /* Rust's ‹std::path::Path› uses ‹OsString› and is just not very pleasant
  * to work with for this task. */
 #[derive(Clone)]
@@ -58,23 +77,35 @@ impl fmt::Display for Path {
     }
 }
 
+/* Owns the tree while it is being built; it is only shared once ‹build›
+ * hands it out. */
 struct DirBuilder {
-    dir: Rc<Directory>,
+    dir: Directory,
 }
 
 impl DirBuilder {
     pub fn new() -> DirBuilder {
         DirBuilder {
-            dir: Rc::new(Directory::default()),
+            dir: Directory::default(),
         }
     }
 
-    pub fn get(&mut self, path: &Path) -> Result<FileBuilder, &'static str> {
-        let mut dir: &mut Directory =
-            Rc::get_mut(&mut self.dir).ok_or("Cannot borrow mutable")?;
+    /* Directory at ‹path›, creating any missing directories on the way. */
+    pub fn get(&mut self, path: &Path) -> Result<FileBuilder<'_>, &'static str> {
+        self._walk(path, true)
+    }
+
+    /* Like ‹get›, but fails if any directory on the way does not exist. */
+    pub fn get_existing(&mut self, path: &Path) -> Result<FileBuilder<'_>, &'static str> {
+        self._walk(path, false)
+    }
+
+    fn _walk(&mut self, path: &Path, create: bool) -> Result<FileBuilder<'_>, &'static str> {
+        let mut dir: &mut Directory = &mut self.dir;
 
         for comp in &path.dirs {
             dir = match dir.entries.entry(comp.to_string()) {
+                Entry::Vacant(_) if !create => return Err("No such directory"),
                 Entry::Vacant(vacant) =>
                     match vacant.insert(INode::Directory(Directory::default())) {
                         INode::Directory(ndir) => ndir,
@@ -92,7 +123,7 @@ impl DirBuilder {
     }
 
     pub fn build(self) -> Rc<Directory> {
-        self.dir
+        Rc::new(self.dir)
     }
 }
 
@@ -167,6 +198,15 @@ impl FromStr for Command {
     }
 }
 
+/* How ‹cd› treats a directory that no listing has declared yet. */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ChDirMode {
+    /* Create the directory on the fly. */
+    Lenient,
+    /* Fail, the trace is inconsistent. */
+    Strict,
+}
+
 #[derive(Debug)]
 pub struct Script(Vec<Command>);
 
@@ -175,7 +215,8 @@ impl Script {
         Script(Vec::new())
     }
 
-    fn run<'a>(&self, root: &'a mut DirBuilder) -> Result<&'a mut DirBuilder, &'static str> {
+    fn run<'a>(&self, root: &'a mut DirBuilder, mode: ChDirMode)
+            -> Result<&'a mut DirBuilder, &'static str> {
         let mut path = Path::new();
         let mut view = root.get(&path)?;
 
@@ -184,7 +225,10 @@ impl Script {
                 Command::List => {}
                 Command::ChDir(arg) => {
                     path.change(arg)?;
-                    view = root.get(&path)?;
+                    view = match mode {
+                        ChDirMode::Lenient => root.get(&path)?,
+                        ChDirMode::Strict => root.get_existing(&path)?,
+                    };
                 }
                 Command::Touch(arg, size) => {
                     view = view.touch(arg, *size)?;
@@ -198,8 +242,8 @@ impl Script {
         Ok(root)
     }
 
-    fn read(file: &File) -> Result<Script, String> {
-        let mut lines = BufReader::new(file).lines();
+    fn read<R: BufRead>(input: R) -> Result<Script, String> {
+        let mut lines = input.lines();
         let mut script = Script::new();
 
         while let Some(line) = aoc::io::read_line(&mut lines) {
@@ -242,35 +286,100 @@ mod inspect {
         btm
     }
 
+    fn _size(dir: &Directory) -> usize {
+        dir.entries.values().map(|inode| match inode {
+            INode::File(s) => *s,
+            INode::Directory(d) => _size(d),
+        }).sum()
+    }
+
+    /* Size of the directory at ‹path›, ‹None› if there is no such directory. */
+    #[allow(dead_code)]
+    pub fn size_at(root: &Directory, path: &Path) -> Option<usize> {
+        let mut dir = root;
+
+        for comp in &path.dirs {
+            dir = match dir.entries.get(comp)? {
+                INode::Directory(d) => d,
+                INode::File(_) => return None,
+            };
+        }
+
+        Some(_size(dir))
+    }
+
+    fn _render(dir: &Directory, depth: usize, lines: &mut Vec<String>) {
+        for (name, inode) in &dir.entries {
+            let indent = "  ".repeat(depth);
+
+            match inode {
+                INode::File(s) => lines.push(format!("{}- {} (file, size={})", indent, name, s)),
+                INode::Directory(d) => {
+                    lines.push(format!("{}- {} (dir)", indent, name));
+                    _render(d, depth + 1, lines);
+                }
+            }
+        }
+    }
+
+    /* Indented listing of the whole tree, one entry per line. */
+    #[allow(dead_code)]
+    pub fn render_tree(root: &Directory) -> String {
+        let mut lines = vec![String::from("- / (dir)")];
+
+        _render(root, 1, &mut lines);
+        lines.join("\n")
+    }
+
     pub fn sum_at_most(root: &Directory, size: usize) -> usize {
         disk_usage(root).values().filter(|m| m <= &&size).sum::<usize>()
     }
 
-    pub fn find_candidate(root: &Directory, capacity: usize, required: usize) -> usize {
+    /* Finds the size of the smallest directory whose deletion frees enough
+     * space, or ‹None› if not even deleting everything would suffice. */
+    pub fn find_candidate(root: &Directory, capacity: usize, required: usize) -> Option<usize> {
         let du = disk_usage(root);
         let used = du.get("/").expect("Root directory not contained");
         let mut sizes = du.values().cloned().collect::<Vec<usize>>();
 
         sizes.sort();
 
-        sizes.iter().skip_while(|n| capacity - used + **n < required).copied()
-            .next().expect("Value missing")
+        sizes.into_iter().find(|n| capacity.saturating_sub(*used) + n >= required)
+    }
+}
+
+#[derive(Debug, Args)]
+struct Options {
+    /// Fail on ‹cd› into a directory that no listing has declared
+    #[arg(long)]
+    strict_cd: bool,
+}
+
+/* ‹None› if no single directory frees enough space. */
+fn solve(root: &Directory, puzzle: Puzzle) -> Option<usize> {
+    match puzzle {
+        Puzzle::P1 => Some(inspect::sum_at_most(root, 100_000)),
+        Puzzle::P2 => inspect::find_candidate(root, 70_000_000, 30_000_000),
     }
 }
 
-fn main() -> IOResult<()> {
-    let args = aoc::args::Arguments::parse();
-    let file = File::open(args.file_name)?;
+fn main() -> Result<(), String> {
+    let (args, options) = aoc::args::Arguments::parse_with::<Options>();
+    let mode = if options.strict_cd { ChDirMode::Strict } else { ChDirMode::Lenient };
 
-    let script = Script::read(&file).expect("Failed to read source");
+    let script = Script::read(args.open()?).expect("Failed to read source");
     let mut builder = DirBuilder::new();
 
-    script.run(&mut builder).expect("Failed to run script");
+    script.run(&mut builder, mode).expect("Failed to run script");
 
-    println!("{}", match args.puzzle {
-        Puzzle::P1 => inspect::sum_at_most(&builder.build(), 100_000),
-        Puzzle::P2 => inspect::find_candidate(&builder.build(), 70_000_000, 30_000_000),
-    });
+    let root = builder.build();
+
+    for puzzle in args.puzzles() {
+        match solve(&root, puzzle) {
+            Some(answer) => println!("{}", answer),
+            None => println!("No suitable directory"),
+        }
+    }
 
     Ok(())
 }
@@ -309,20 +418,118 @@ mod tests {
 
     fn example_fs() -> Rc<Directory> {
         let mut builder = DirBuilder::new();
-        example_sh().run(&mut builder).expect("Cannot construct example structure");
+        example_sh().run(&mut builder, ChDirMode::Strict).expect("Cannot construct example structure");
         builder.build()
     }
 
+    fn path(s: &str) -> Path {
+        let mut path = Path::new();
+        for comp in s.split('/').filter(|c| !c.is_empty()) {
+            path.change(comp).expect("Invalid path component");
+        }
+
+        path
+    }
+
+    #[test]
+    fn shared_build() {
+        let root = example_fs();
+        let shared = Rc::clone(&root);
+
+        assert_eq!(inspect::disk_usage(&root)["/"], 48381165);
+        assert_eq!(inspect::disk_usage(&shared)["/a"], 94853);
+
+        let mut builder = DirBuilder::new();
+        example_sh().run(&mut builder, ChDirMode::Lenient).expect("Cannot construct example structure");
+        assert!(builder.get(&Path::new()).is_ok());
+
+        let again = builder.build();
+        let _keep = Rc::clone(&again);
+        assert_eq!(inspect::sum_at_most(&again, 100_000), 95_437);
+    }
+
+    #[test]
+    fn cd_undeclared() {
+        let script = Script(vec![
+            Command::ChDir(String::from("/")),
+            Command::ChDir(String::from("x")),
+            Command::Touch(String::from("y"), 42),
+        ]);
+
+        let mut builder = DirBuilder::new();
+        assert_eq!(script.run(&mut builder, ChDirMode::Strict).err(), Some("No such directory"));
+
+        let mut builder = DirBuilder::new();
+        script.run(&mut builder, ChDirMode::Lenient).expect("Lenient mode creates directories");
+        assert_eq!(inspect::disk_usage(&builder.build())["/x"], 42);
+    }
+
+    #[test]
+    fn size_at() {
+        let root = example_fs();
+
+        assert_eq!(inspect::size_at(&root, &path("/a/e")), Some(584));
+        assert_eq!(inspect::size_at(&root, &path("/a")), Some(94853));
+        assert_eq!(inspect::size_at(&root, &path("/")), Some(48381165));
+
+        assert_eq!(inspect::size_at(&root, &path("/a/x")), None);
+        assert_eq!(inspect::size_at(&root, &path("/b.txt")), None);
+    }
+
+    #[test]
+    fn render_tree() {
+        let expected = [
+            "- / (dir)",
+            "  - a (dir)",
+            "    - e (dir)",
+            "      - i (file, size=584)",
+            "    - f (file, size=29116)",
+            "    - g (file, size=2557)",
+            "    - h.lst (file, size=62596)",
+            "  - b.txt (file, size=14848514)",
+            "  - c.dat (file, size=8504156)",
+            "  - d (dir)",
+            "    - d.ext (file, size=5626152)",
+            "    - d.log (file, size=8033020)",
+            "    - j (file, size=4060174)",
+            "    - k (file, size=7214296)",
+        ];
+
+        assert_eq!(inspect::render_tree(&example_fs()), expected.join("\n"));
+        assert_eq!(inspect::render_tree(&Directory::default()), "- / (dir)");
+    }
+
+    #[test]
+    fn remove() {
+        let mut root = Rc::try_unwrap(example_fs()).expect("Example is not shared");
+
+        assert!(matches!(root.remove(&path("/a/e/i")), Ok(INode::File(584))));
+        let du = inspect::disk_usage(&root);
+        assert_eq!(du["/a/e"], 0);
+        assert_eq!(du["/"], 48381165 - 584);
+
+        assert!(matches!(root.remove(&path("/d")), Ok(INode::Directory(_))));
+        let du = inspect::disk_usage(&root);
+        assert!(!du.contains_key("/d"));
+        assert_eq!(du["/"], 48381165 - 584 - 24933642);
+
+        assert!(root.remove(&path("/d")).is_err());
+        assert!(root.remove(&path("/b.txt/x")).is_err());
+        assert!(root.remove(&path("/")).is_err());
+    }
+
     #[test]
     fn example1() {
-        assert_eq!(inspect::sum_at_most(&example_fs(), 100_000), 95_437);
+        assert_eq!(solve(&example_fs(), Puzzle::P1), Some(95_437));
     }
 
     #[test]
     fn example2() {
-        assert_eq!(
-            inspect::find_candidate(&example_fs(), 70_000_000, 30_000_000),
-            24933642
-        );
+        assert_eq!(solve(&example_fs(), Puzzle::P2), Some(24933642));
+    }
+
+    #[test]
+    fn no_candidate() {
+        assert_eq!(inspect::find_candidate(&example_fs(), 50_000_000, 60_000_000), None);
     }
 }
@@ -1,32 +1,25 @@
 use aoc::args::Puzzle;
 use aoc::euclid::{CoordGenerator, Direction, DirectionIterator, Point, Vector};
 use aoc::matrix::{Matrix};
-use std::cmp::max;
+use std::cmp::{max, Reverse};
 use std::collections::BinaryHeap;
-use std::fs::File;
-use std::io::Result as IOResult;
-use std::io::{BufRead, BufReader};
+use std::io::BufRead;
 
 struct Map(Matrix<u32>);
-struct BitLayer(Matrix<bool>);
 
 impl Map {
-    pub fn new(width: usize, height: usize) -> Self {
-        Self(Matrix::new(width, height))
-    }
-
-    fn layer(&self, dir: &Direction) -> BitLayer {
-        let mut layer = BitLayer::new(self.0.width, self.0.height);
+    fn layer(&self, dir: &Direction) -> Matrix<bool> {
+        let mut layer = self.0.map(|_| false);
         let mut last = Point::new(-1, -1);
         let mut max_elevation = 0;
 
         for coord in CoordGenerator::new(dir, self.0.width, self.0.height) {
             /* If both coords change, we have a different set of points. */
             if coord.x != last.x && coord.y != last.y {
-                layer.0[coord] = true;
+                layer[coord] = true;
                 max_elevation = self.0[coord];
             } else {
-                layer.0[coord] = self.0[coord] > max_elevation;
+                layer[coord] = self.0[coord] > max_elevation;
                 max_elevation = max(max_elevation, self.0[coord])
             }
 
@@ -36,12 +29,20 @@ impl Map {
         layer
     }
 
-    pub fn elevated_points(&self) -> usize {
+    /* Trees visible from outside the grid, row by row. */
+    pub fn visible_coords(&self) -> Vec<Point> {
         DirectionIterator::new()
             .map(|dir| self.layer(&dir))
-            .fold(BitLayer::new(self.0.width, self.0.height),
-                    |acc, el| BitLayer::or(&acc, &el))
-            .bits(true)
+            .fold(self.0.map(|_| false),
+                    |acc, el| Matrix::fold(&acc, &el, |p, q| *p || *q))
+            .iter_cells()
+            .filter(|(_, visible)| **visible)
+            .map(|(coord, _)| coord)
+            .collect()
+    }
+
+    pub fn elevated_points(&self) -> usize {
+        self.visible_coords().len()
     }
 
     fn is_border(&self, coord: &Point) -> bool {
@@ -58,6 +59,9 @@ impl Map {
         let elev = self.0[*coord];
         let mut cursor: Point = *coord;
 
+        // Every step counts the tree *ahead* of the cursor, so the tree that
+        // stops the loop, be it on the border or at least as tall as the
+        // viewer, has already been counted.
         let mut count: usize = 0;
         while !self.is_border(&cursor) && (cursor == *coord || self.0[cursor] < elev) {
             count += 1;
@@ -67,94 +71,73 @@ impl Map {
         count
     }
 
-    fn scenic_scores(&self) -> usize {
-        let mut heap: BinaryHeap<usize> = BinaryHeap::new();
+    fn scenic_score(&self, coord: &Point) -> usize {
+        DirectionIterator::DELTAS.iter()
+            .map(|v| self.scenic_ray(coord, v))
+            .product()
+    }
 
-        for coord in CoordGenerator::new(
-                &Direction::North, self.0.width, self.0.height) {
-            heap.push(
-                DirectionIterator::new()
-                    .map(|d| self.scenic_ray(&coord, &Vector::from(&d)))
-                    .product(),
-            );
-
-            if heap.len() > 1000 {
-                heap.shrink_to(100);
-            }
+    /* Scenic score of every tree, zero on the border. */
+    fn scenic_matrix(&self) -> Matrix<usize> {
+        let mut scores = Matrix::new(self.0.width, self.0.height);
+
+        for (coord, score) in scores.iter_cells_mut() {
+            *score = self.scenic_score(&coord);
         }
 
-        heap.pop().expect("No elements found")
+        scores
     }
-}
 
-impl BitLayer {
-    pub fn new(width: usize, height: usize) -> Self {
-        Self(Matrix::new(width, height))
+    fn scenic_scores(&self) -> usize {
+        self.scenic_matrix().iter_cells()
+            .map(|(_, score)| *score)
+            .max()
+            .expect("No elements found")
     }
 
-    pub fn or(a: &Self, b: &Self) -> Self {
-        Self(Matrix::fold(&a.0, &b.0, |p, q| *p || *q))
-    }
+    /* Returns ‹k› best trees, highest score first, ties broken by position. */
+    #[allow(dead_code)]
+    fn top_scenic(&self, k: usize) -> Vec<(Point, usize)> {
+        let mut heap: BinaryHeap<(Reverse<usize>, Point)> = BinaryHeap::new();
 
-    pub fn bits(&self, b: bool) -> usize {
-        let mut counter: usize = 0;
-        for row in &self.0.data {
-            for cell in row {
-                if *cell == b {
-                    counter += 1;
-                }
+        for coord in CoordGenerator::new(
+                &Direction::North, self.0.width, self.0.height) {
+            heap.push((Reverse(self.scenic_score(&coord)), coord));
+
+            if heap.len() > k {
+                heap.pop();
             }
         }
 
-        counter
+        heap.into_sorted_vec().into_iter()
+            .map(|(Reverse(score), coord)| (coord, score))
+            .collect()
     }
 }
 
-fn read_map(file: &File) -> Result<Map, String> {
-    let mut line_reader = BufReader::new(file).lines();
-    let mut lines: Vec<String> = Vec::new();
-
-    while let Some(line) = aoc::io::read_line(&mut line_reader) {
-        if line.chars().any(|c| !c.is_ascii_digit()) {
-            return Err(String::from("Invalid line: ") + line.as_str());
-        }
-
-        lines.push(line);
-    }
-
-    if lines.is_empty() {
-        return Err(String::from("Empty input"));
-    }
-
-    if lines.iter().any(|l| l.len() != lines[0].len()) {
-        return Err(String::from("Lines of input do not have the same size"));
-    }
-
-    let mut map = Map::new(lines[0].len(), lines.len());
+fn read_map<R: BufRead>(input: R) -> Result<Map, String> {
+    let lines = aoc::io::read_nonblank_block(&mut input.lines());
+    let matrix = Matrix::from_lines(&lines, |c| {
+        c.to_digit(10).ok_or_else(|| format!("Invalid digit {}", c))
+    })?;
 
-    let mut coord = Point::new(0, 0);
-    for row in &lines {
-        for c in row.chars() {
-            map.0[coord] = c.to_digit(10).expect("BUG: Invalid digit found");
-            coord.x += 1;
-        }
+    Ok(Map(matrix))
+}
 
-        coord = Point::new(0, coord.y + 1);
+fn solve(map: &Map, puzzle: Puzzle) -> usize {
+    match puzzle {
+        Puzzle::P1 => map.elevated_points(),
+        Puzzle::P2 => map.scenic_scores(),
     }
-
-    Ok(map)
 }
 
-fn main() -> IOResult<()> {
+fn main() -> Result<(), String> {
     let args = aoc::args::Arguments::parse();
-    let file = File::open(args.file_name)?;
-
-    let map = read_map(&file).expect("Cannot read input");
+    let map = read_map(args.open()?).expect("Cannot read input");
 
-    println!("{}", match args.puzzle {
-        Puzzle::P1 => map.elevated_points(),
-        Puzzle::P2 => map.scenic_scores(),
-    });
+    for puzzle in args.puzzles() {
+        println!("{}", solve(&map, puzzle));
+    }
 
     Ok(())
 }
@@ -205,11 +188,93 @@ mod tests {
 
     #[test]
     fn example1() {
-        assert_eq!(example_matrix().elevated_points(), 21);
+        assert_eq!(solve(&example_matrix(), Puzzle::P1), 21);
+    }
+
+    #[test]
+    fn visible_coords() {
+        let map = example_matrix();
+        let visible = map.visible_coords();
+
+        assert_eq!(visible.len(), 21);
+        assert!(visible.contains(&Point::new(2, 1)));
+        assert_eq!(map.0[Point::new(2, 1)], 5);
+
+        assert!(!visible.contains(&Point::new(2, 2)));
+        assert!(!visible.contains(&Point::new(3, 1)));
     }
 
     #[test]
     fn example2() {
-        assert_eq!(example_matrix().scenic_scores(), 8);
+        assert_eq!(solve(&example_matrix(), Puzzle::P2), 8);
+    }
+
+    #[test]
+    fn scenic_matrix() {
+        let map = example_matrix();
+        let scores = map.scenic_matrix();
+
+        assert_eq!(scores[Point::new(2, 3)], 8);
+        assert_eq!(scores[Point::new(2, 1)], 4);
+        assert_eq!(scores.row(0), Some(&[0, 0, 0, 0, 0][..]));
+        assert_eq!(scores.column(4).collect::<Vec<_>>(), vec![&0; 5]);
+    }
+
+    #[test]
+    fn scenic_blocked_by_equal() {
+        let map = Map(Matrix {
+            width: 7,
+            height: 3,
+            data: vec![
+                vec![0, 0, 0, 0, 0, 0, 0],
+                vec![1, 5, 2, 5, 3, 1, 1],
+                vec![0, 0, 0, 0, 0, 0, 0],
+            ],
+        });
+
+        let east = Vector::new(1, 0);
+        let west = Vector::new(-1, 0);
+
+        assert_eq!(map.scenic_ray(&Point::new(1, 1), &east), 2);
+        assert_eq!(map.scenic_ray(&Point::new(3, 1), &west), 2);
+        assert_eq!(map.scenic_ray(&Point::new(3, 1), &east), 3);
+        assert_eq!(map.scenic_ray(&Point::new(2, 1), &east), 1);
+        assert_eq!(map.scenic_score(&Point::new(3, 1)), 6);
+    }
+
+    #[test]
+    fn example2_top() {
+        assert_eq!(example_matrix().top_scenic(3), vec![
+            (Point::new(2, 3), 8),
+            (Point::new(1, 2), 6),
+            (Point::new(2, 1), 4),
+        ]);
+    }
+
+    fn generated(width: usize, height: usize, seed: u64) -> Map {
+        let mut state = seed;
+        let mut matrix = Matrix::new(width, height);
+
+        for (_, cell) in matrix.iter_cells_mut() {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            *cell = ((state >> 33) % 10) as u32;
+        }
+
+        Map(matrix)
+    }
+
+    #[test]
+    fn top_scenic_large() {
+        for map in [generated(120, 90, 7), generated(64, 64, 1), generated(100, 100, 0)] {
+            let mut all = CoordGenerator::new(&Direction::East, map.0.width, map.0.height)
+                .map(|p| map.scenic_score(&p))
+                .collect::<Vec<usize>>();
+            all.sort_by(|a, b| b.cmp(a));
+
+            assert_eq!(map.scenic_scores(), all[0]);
+
+            let top = map.top_scenic(2000);
+            assert_eq!(top.iter().map(|(_, score)| *score).collect::<Vec<_>>(), all[.. 2000]);
+        }
     }
 }
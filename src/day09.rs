@@ -1,10 +1,9 @@
 use aoc::args::Puzzle;
 use aoc::euclid::{Direction, Point, Vector};
+use clap::Args;
 use std::collections::BTreeMap;
 use std::collections::btree_map::Entry;
-use std::fs::File;
-use std::io::Result as IOResult;
-use std::io::{BufRead, BufReader};
+use std::io::BufRead;
 use std::str::FromStr;
 
 #[derive(Debug, PartialEq, Eq)]
@@ -112,12 +111,22 @@ impl Simulation {
     }
 
     fn run(&self, rope: &mut Rope, observer: &mut dyn Observer) {
-        observer.observe(rope);
+        self.run_multi(rope, &mut [observer]);
+    }
+
+    fn run_multi(&self, rope: &mut Rope, observers: &mut [&mut dyn Observer]) {
+        let mut notify = |rope: &Rope| {
+            for observer in observers.iter_mut() {
+                observer.observe(rope);
+            }
+        };
+
+        notify(rope);
 
         for motion in &self.0 {
             for _ in 0 .. motion.count {
                 rope.walk(motion.dir);
-                observer.observe(rope);
+                notify(rope);
             }
         }
     }
@@ -155,8 +164,28 @@ impl Observer for TailObserver {
     }
 }
 
-fn read_simulation(file: &File) -> Result<Simulation, String> {
-    let mut line_reader = BufReader::new(file).lines();
+#[derive(Debug, Args)]
+struct Options {
+    /// Initial position of all knots, as ‹X,Y›
+    #[arg(long, default_value = "0,0", allow_hyphen_values = true)]
+    start: Point,
+}
+
+/* Records the knots of the rope after every step. */
+#[allow(dead_code)]
+#[derive(Default)]
+struct TraceObserver {
+    trace: Vec<Vec<Point>>,
+}
+
+impl Observer for TraceObserver {
+    fn observe(&mut self, rope: &Rope) {
+        self.trace.push(rope.knots.clone());
+    }
+}
+
+fn read_simulation<R: BufRead>(input: R) -> Result<Simulation, String> {
+    let mut line_reader = input.lines();
 
     let mut sim: Simulation = Simulation::new();
     while let Some(line) = aoc::io::read_line(&mut line_reader) {
@@ -166,19 +195,23 @@ fn read_simulation(file: &File) -> Result<Simulation, String> {
     Ok(sim)
 }
 
-fn main() -> IOResult<()> {
-    let args = aoc::args::Arguments::parse();
-    let file = File::open(args.file_name)?;
-
-    let simulation = read_simulation(&file).expect("Cannot parse simulation");
+fn solve(simulation: &Simulation, start: &Point, puzzle: Puzzle) -> usize {
     let mut observer = TailObserver::new();
-    let mut rope = match args.puzzle {
-        Puzzle::P1 => Rope::new(&Point::new(0, 0), 2),
-        Puzzle::P2 => Rope::new(&Point::new(0, 0), 10),
+    let mut rope = match puzzle {
+        Puzzle::P1 => Rope::new(start, 2),
+        Puzzle::P2 => Rope::new(start, 10),
     };
 
     simulation.run(&mut rope, &mut observer);
-    println!("{}", observer.result());
+    observer.result()
+}
+
+fn main() -> Result<(), String> {
+    let (args, options) = aoc::args::Arguments::parse_with::<Options>();
+    let simulation = read_simulation(args.open()?).expect("Cannot parse simulation");
+    for puzzle in args.puzzles() {
+        println!("{}", solve(&simulation, &options.start, puzzle));
+    }
 
     Ok(())
 }
@@ -220,22 +253,31 @@ mod tests {
 
     #[test]
     fn example1() {
-        let mut observer = TailObserver::new();
-        let mut start = Rope::new(&Point::new(0, 0), 2);
-
-        example_sim().run(&mut start, &mut observer);
+        assert_eq!(solve(&example_sim(), &Point::new(0, 0), Puzzle::P1), 13);
+    }
 
-        assert_eq!(observer.result(), 13);
+    #[test]
+    fn example1_shifted() {
+        assert_eq!(solve(&example_sim(), &Point::new(100, -50), Puzzle::P1), 13);
     }
 
     #[test]
     fn example1_10() {
-        let mut observer = TailObserver::new();
-        let mut start = Rope::new(&Point::new(0, 0), 10);
+        assert_eq!(solve(&example_sim(), &Point::new(0, 0), Puzzle::P2), 1);
+    }
 
-        example_sim().run(&mut start, &mut observer);
+    #[test]
+    fn example1_multi() {
+        let mut tail = TailObserver::new();
+        let mut trace = TraceObserver::default();
+        let mut start = Rope::new(&Point::new(0, 0), 2);
 
-        assert_eq!(observer.result(), 1);
+        example_sim().run_multi(&mut start, &mut [&mut tail, &mut trace]);
+
+        assert_eq!(tail.result(), 13);
+        assert_eq!(trace.trace.len(), 25);
+        assert_eq!(trace.trace[0], vec![Point::new(0, 0); 2]);
+        assert_eq!(trace.trace.last(), Some(&vec![Point::new(2, 2), Point::new(1, 2)]));
     }
 
     fn example_sim2() -> Simulation {
@@ -253,11 +295,6 @@ mod tests {
 
     #[test]
     fn example2() {
-        let mut observer = TailObserver::new();
-        let mut start = Rope::new(&Point::new(0, 0), 10);
-
-        example_sim2().run(&mut start, &mut observer);
-
-        assert_eq!(observer.result(), 36);
+        assert_eq!(solve(&example_sim2(), &Point::new(0, 0), Puzzle::P2), 36);
     }
 }
@@ -1,7 +1,6 @@
 use aoc::args::Puzzle;
-use std::fs::File;
-use std::io::Result as IOResult;
-use std::io::{BufRead, BufReader};
+use std::io::BufRead;
+use std::str::FromStr;
 
 #[derive(Debug)]
 enum Instruction {
@@ -9,26 +8,71 @@ enum Instruction {
     NoOp,
 }
 
+impl FromStr for Instruction {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        if line == "noop" {
+            return Ok(Instruction::NoOp);
+        }
+
+        match line.split_once(' ') {
+            Some(("addx", right)) => {
+                let num = right.parse().map_err(|_| String::from("Invalid number: ") + right)?;
+                Ok(Instruction::AddX(num))
+            }
+            Some(_) => Err(String::from("Bad instruction: ") + line),
+            None => Err(String::from("Invalid line: ") + line),
+        }
+    }
+}
+
 struct Program(Vec<Instruction>);
 
+impl FromStr for Program {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.lines()
+            .map(|line| line.parse::<Instruction>())
+            .collect::<Result<Vec<_>, _>>()
+            .map(Program)
+    }
+}
+
 impl Program {
     fn new() -> Self {
         Program(Vec::new())
     }
 
-    fn exec(&self) -> SignalIterator<std::slice::Iter<Instruction>> {
+    fn exec(&self) -> SignalIterator<'_, std::slice::Iter<'_, Instruction>> {
         SignalIterator::new(self.0.iter())
     }
 
-    fn draw(&self) {
+    fn pixel(cycle: usize, x: isize) -> char {
+        let pos = (cycle % 40) as isize;
+        if x - 1 <= pos && pos <= x + 1 { '█' } else { ' ' }
+    }
+
+    /* Sum of signal strengths at cycles 20, 60, …, 220 and the rendered CRT,
+     * computed in a single run of the program. */
+    fn run_both(&self) -> (isize, String) {
+        let mut strength = 0;
+        let mut screen = String::new();
+
         for (cycle, x) in self.exec().enumerate() {
-            let pos = (cycle % 40) as isize;
-            print!("{}", if x - 1 <= pos && pos <= x + 1 { '█' } else { ' ' });
+            let number = cycle as isize + 1;
+            if number <= 220 && number % 40 == 20 {
+                strength += number * x;
+            }
 
+            screen.push(Self::pixel(cycle, x));
             if cycle % 40 == 39 {
-                println!();
+                screen.push('\n');
             }
         }
+
+        (strength, screen)
     }
 }
 
@@ -97,46 +141,40 @@ impl<'a, ProgIt: Iterator> Iterator for SignalIterator<'a, ProgIt>
     }
 }
 
-fn read_program(file: &File) -> Result<Program, String> {
-    let mut line_reader = BufReader::new(file).lines();
+fn read_program<R: BufRead>(input: R) -> Result<Program, String> {
+    let mut line_reader = input.lines();
 
     let mut program: Program = Program::new();
     while let Some(line) = aoc::io::read_line(&mut line_reader) {
-        if line == "noop" {
-            program.0.push(Instruction::NoOp);
-        } else if let Some((left, right)) = line.split_once(' ') {
-            if left != "addx" {
-                return Err(String::from("Bad instruction: ") + &line);
-            }
-
-            let num = right.parse().map_err(|_| String::from("Invalid number: ") + right)?;
-            program.0.push(Instruction::AddX(num));
-        } else {
-            return Err(String::from("Invalid line: ") + &line);
-        }
+        program.0.push(line.parse()?);
     }
 
     Ok(program)
 }
 
-fn main() -> IOResult<()> {
-    let args = aoc::args::Arguments::parse();
-    let file = File::open(args.file_name)?;
-
-    let program = read_program(&file).expect("Cannot read program");
-
-    match args.puzzle {
+/* The answer as printed: the sum of signal strengths, or the CRT screen
+ * without its final newline. */
+fn solve(program: &Program, puzzle: Puzzle) -> String {
+    match puzzle {
         Puzzle::P1 => {
-            let iter = program.exec();
             let points = (20..=220).step_by(40).collect::<Vec<isize>>();
-            println!("{}", iter.measure(&points).iter().sum::<isize>());
-        },
-
-        Puzzle::P2 => {
-            program.draw();
+            program.exec().measure(&points).iter().sum::<isize>().to_string()
         }
+        Puzzle::P2 => program.run_both().1.trim_end_matches('\n').to_string(),
+    }
+}
+
+fn main() -> Result<(), String> {
+    let args = aoc::args::Arguments::parse();
+    let program = read_program(args.open()?).expect("Cannot read program");
+
+    if args.both {
+        let (strength, screen) = program.run_both();
+        print!("{}\n{}", strength, screen);
+        return Ok(());
     }
 
+    println!("{}", solve(&program, args.puzzle));
     Ok(())
 }
 
@@ -153,154 +191,154 @@ mod tests {
     }
 
     fn data2() -> Program {
-        Program(vec![
-            Instruction::AddX(15),
-            Instruction::AddX(-11),
-            Instruction::AddX(6),
-            Instruction::AddX(-3),
-            Instruction::AddX(5),
-            Instruction::AddX(-1),
-            Instruction::AddX(-8),
-            Instruction::AddX(13),
-            Instruction::AddX(4),
-            Instruction::NoOp,
-            Instruction::AddX(-1),
-            Instruction::AddX(5),
-            Instruction::AddX(-1),
-            Instruction::AddX(5),
-            Instruction::AddX(-1),
-            Instruction::AddX(5),
-            Instruction::AddX(-1),
-            Instruction::AddX(5),
-            Instruction::AddX(-1),
-            Instruction::AddX(-35),
-            Instruction::AddX(1),
-            Instruction::AddX(24),
-            Instruction::AddX(-19),
-            Instruction::AddX(1),
-            Instruction::AddX(16),
-            Instruction::AddX(-11),
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::AddX(21),
-            Instruction::AddX(-15),
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::AddX(-3),
-            Instruction::AddX(9),
-            Instruction::AddX(1),
-            Instruction::AddX(-3),
-            Instruction::AddX(8),
-            Instruction::AddX(1),
-            Instruction::AddX(5),
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::AddX(-36),
-            Instruction::NoOp,
-            Instruction::AddX(1),
-            Instruction::AddX(7),
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::AddX(2),
-            Instruction::AddX(6),
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::AddX(1),
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::AddX(7),
-            Instruction::AddX(1),
-            Instruction::NoOp,
-            Instruction::AddX(-13),
-            Instruction::AddX(13),
-            Instruction::AddX(7),
-            Instruction::NoOp,
-            Instruction::AddX(1),
-            Instruction::AddX(-33),
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::AddX(2),
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::AddX(8),
-            Instruction::NoOp,
-            Instruction::AddX(-1),
-            Instruction::AddX(2),
-            Instruction::AddX(1),
-            Instruction::NoOp,
-            Instruction::AddX(17),
-            Instruction::AddX(-9),
-            Instruction::AddX(1),
-            Instruction::AddX(1),
-            Instruction::AddX(-3),
-            Instruction::AddX(11),
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::AddX(1),
-            Instruction::NoOp,
-            Instruction::AddX(1),
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::AddX(-13),
-            Instruction::AddX(-19),
-            Instruction::AddX(1),
-            Instruction::AddX(3),
-            Instruction::AddX(26),
-            Instruction::AddX(-30),
-            Instruction::AddX(12),
-            Instruction::AddX(-1),
-            Instruction::AddX(3),
-            Instruction::AddX(1),
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::AddX(-9),
-            Instruction::AddX(18),
-            Instruction::AddX(1),
-            Instruction::AddX(2),
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::AddX(9),
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::AddX(-1),
-            Instruction::AddX(2),
-            Instruction::AddX(-37),
-            Instruction::AddX(1),
-            Instruction::AddX(3),
-            Instruction::NoOp,
-            Instruction::AddX(15),
-            Instruction::AddX(-21),
-            Instruction::AddX(22),
-            Instruction::AddX(-6),
-            Instruction::AddX(1),
-            Instruction::NoOp,
-            Instruction::AddX(2),
-            Instruction::AddX(1),
-            Instruction::NoOp,
-            Instruction::AddX(-10),
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::AddX(20),
-            Instruction::AddX(1),
-            Instruction::AddX(2),
-            Instruction::AddX(2),
-            Instruction::AddX(-6),
-            Instruction::AddX(-11),
-            Instruction::NoOp,
-            Instruction::NoOp,
-            Instruction::NoOp,
-        ])
+        "\
+        addx 15\n\
+        addx -11\n\
+        addx 6\n\
+        addx -3\n\
+        addx 5\n\
+        addx -1\n\
+        addx -8\n\
+        addx 13\n\
+        addx 4\n\
+        noop\n\
+        addx -1\n\
+        addx 5\n\
+        addx -1\n\
+        addx 5\n\
+        addx -1\n\
+        addx 5\n\
+        addx -1\n\
+        addx 5\n\
+        addx -1\n\
+        addx -35\n\
+        addx 1\n\
+        addx 24\n\
+        addx -19\n\
+        addx 1\n\
+        addx 16\n\
+        addx -11\n\
+        noop\n\
+        noop\n\
+        addx 21\n\
+        addx -15\n\
+        noop\n\
+        noop\n\
+        addx -3\n\
+        addx 9\n\
+        addx 1\n\
+        addx -3\n\
+        addx 8\n\
+        addx 1\n\
+        addx 5\n\
+        noop\n\
+        noop\n\
+        noop\n\
+        noop\n\
+        noop\n\
+        addx -36\n\
+        noop\n\
+        addx 1\n\
+        addx 7\n\
+        noop\n\
+        noop\n\
+        noop\n\
+        addx 2\n\
+        addx 6\n\
+        noop\n\
+        noop\n\
+        noop\n\
+        noop\n\
+        noop\n\
+        addx 1\n\
+        noop\n\
+        noop\n\
+        addx 7\n\
+        addx 1\n\
+        noop\n\
+        addx -13\n\
+        addx 13\n\
+        addx 7\n\
+        noop\n\
+        addx 1\n\
+        addx -33\n\
+        noop\n\
+        noop\n\
+        noop\n\
+        addx 2\n\
+        noop\n\
+        noop\n\
+        noop\n\
+        addx 8\n\
+        noop\n\
+        addx -1\n\
+        addx 2\n\
+        addx 1\n\
+        noop\n\
+        addx 17\n\
+        addx -9\n\
+        addx 1\n\
+        addx 1\n\
+        addx -3\n\
+        addx 11\n\
+        noop\n\
+        noop\n\
+        addx 1\n\
+        noop\n\
+        addx 1\n\
+        noop\n\
+        noop\n\
+        addx -13\n\
+        addx -19\n\
+        addx 1\n\
+        addx 3\n\
+        addx 26\n\
+        addx -30\n\
+        addx 12\n\
+        addx -1\n\
+        addx 3\n\
+        addx 1\n\
+        noop\n\
+        noop\n\
+        noop\n\
+        addx -9\n\
+        addx 18\n\
+        addx 1\n\
+        addx 2\n\
+        noop\n\
+        noop\n\
+        addx 9\n\
+        noop\n\
+        noop\n\
+        noop\n\
+        addx -1\n\
+        addx 2\n\
+        addx -37\n\
+        addx 1\n\
+        addx 3\n\
+        noop\n\
+        addx 15\n\
+        addx -21\n\
+        addx 22\n\
+        addx -6\n\
+        addx 1\n\
+        noop\n\
+        addx 2\n\
+        addx 1\n\
+        noop\n\
+        addx -10\n\
+        noop\n\
+        noop\n\
+        addx 20\n\
+        addx 1\n\
+        addx 2\n\
+        addx 2\n\
+        addx -6\n\
+        addx -11\n\
+        noop\n\
+        noop\n\
+        noop\n"
+            .parse().expect("Invalid program")
     }
 
     #[test]
@@ -315,6 +353,16 @@ mod tests {
         assert_eq!(iter.next(), None);
     }
 
+    #[test]
+    fn parse_small() {
+        let program: Program = "noop\naddx 3\naddx -5\n".parse().expect("Valid program");
+        assert_eq!(program.exec().collect::<Vec<isize>>(), vec![1, 1, 1, 4, 4]);
+
+        assert!("addx".parse::<Program>().is_err());
+        assert!("mulx 3".parse::<Program>().is_err());
+        assert!("addx three".parse::<Program>().is_err());
+    }
+
     #[test]
     fn example2() {
         let data1 = data2();
@@ -323,11 +371,23 @@ mod tests {
             vec![420, 1140, 1800, 2940, 2880, 3960]);
     }
 
+    #[test]
+    fn example2_both() {
+        let (strength, screen) = data2().run_both();
+
+        assert_eq!(strength, 13140);
+        assert_eq!(screen.replace('█', "#").replace(' ', "."), "\
+            ##..##..##..##..##..##..##..##..##..##..\n\
+            ###...###...###...###...###...###...###.\n\
+            ####....####....####....####....####....\n\
+            #####.....#####.....#####.....#####.....\n\
+            ######......######......######......####\n\
+            #######.......#######.......#######.....\n");
+        assert_eq!(solve(&data2(), Puzzle::P2) + "\n", screen);
+    }
+
     #[test]
     fn example2_sum() {
-        let data2 = data2();
-        let iter = data2.exec();
-        assert_eq!(iter.measure(&(20..=220).step_by(40).collect::<Vec<isize>>()).iter().sum::<isize>(),
-            13140);
+        assert_eq!(solve(&data2(), Puzzle::P1), "13140");
     }
 }
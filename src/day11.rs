@@ -1,7 +1,6 @@
 use aoc::args::Puzzle;
 use std::cell::RefCell;
 use std::collections::{BinaryHeap, VecDeque};
-use std::fs::File;
 use std::str::FromStr;
 
 extern crate pest;
@@ -12,7 +11,7 @@ use pest::Parser;
 
 type WorryLevel = isize;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct MonkeyTest {
     divisor: WorryLevel,
     if_true: MonkeyID,
@@ -31,7 +30,7 @@ impl MonkeyTest {
 
 type MonkeyID = usize;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum Operation<T> {
     Add(T),
     Mul(T),
@@ -48,7 +47,7 @@ impl Operation<WorryLevel> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Monkey {
     items: VecDeque<WorryLevel>,
     op: Operation<WorryLevel>,
@@ -69,15 +68,6 @@ impl WorryLevelManagement for DroppingWLM {
     }
 }
 
-#[derive(Default)]
-struct NoWLM;
-
-impl WorryLevelManagement for NoWLM {
-    fn manage(&self, n: WorryLevel) -> WorryLevel {
-        n
-    }
-}
-
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Default)]
 struct LCMWLM {
@@ -122,7 +112,7 @@ impl Monkey {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 struct MonkeyParty(Vec<RefCell<Monkey>>);
 
 impl MonkeyParty {
@@ -147,7 +137,7 @@ impl MonkeyParty {
     }
 
     fn round<WLM: WorryLevelManagement>(&mut self, wlm: &WLM) {
-        for (_, mc) in self.0.iter().enumerate() {
+        for mc in self.0.iter() {
             self.turn(&mut mc.borrow_mut(), wlm);
         }
     }
@@ -240,7 +230,7 @@ impl PartyParser {
 
     fn run(str: &str) -> Result<MonkeyParty, String> {
         let monkeys = Self::uw(Self::parse(Rule::monkey_party, str))?
-            .next().unwrap().into_inner().into_iter().next().unwrap();
+            .next().unwrap().into_inner().next().unwrap();
 
         let mut party = MonkeyParty::default();
         for monkey in monkeys.into_inner() {
@@ -252,12 +242,23 @@ impl PartyParser {
     }
 }
 
+/* Plays the rounds on a copy of the party, so that it can be solved again. */
+fn solve(party: &MonkeyParty, puzzle: Puzzle) -> usize {
+    let mut party = party.clone();
+
+    match puzzle {
+        Puzzle::P1 => party.rounds(20, &DroppingWLM),
+        Puzzle::P2 => party.rounds(10000, &LCMWLM::new(&party)),
+    }
+
+    party.business()
+}
+
 fn main() -> Result<(), String> {
     let args = aoc::args::Arguments::parse();
-    let file = File::open(args.file_name).expect("Cannot open file");
-    let text = aoc::io::read_file(file).expect("Cannot read file");
+    let text = aoc::io::read_capped_from(args.open()?, 1 << 20)?;
 
-    let mut mp = match PartyParser::run(&text) {
+    let mp = match PartyParser::run(&text) {
         Err(s) => {
             println!("{}", &s);
             return Err("Parser failed".into());
@@ -266,12 +267,9 @@ fn main() -> Result<(), String> {
         Ok(mp) => mp,
     };
 
-    match args.puzzle {
-        Puzzle::P1 => mp.rounds(20, &DroppingWLM::default()),
-        Puzzle::P2 => mp.rounds(10000, &LCMWLM::new(&mp)),
+    for puzzle in args.puzzles() {
+        println!("{}", solve(&mp, puzzle));
     }
-
-    println!("{}", mp.business());
     Ok(())
 }
 
@@ -291,7 +289,7 @@ mod tests {
     #[test]
     fn throws() {
         let example = example();
-        let wlm = DroppingWLM::default();
+        let wlm = DroppingWLM;
 
         assert_eq!(example.0[0].borrow_mut().throw(&wlm), Some((500, 3)));
         assert_eq!(example.0[0].borrow_mut().throw(&wlm), Some((620, 3)));
@@ -308,7 +306,7 @@ mod tests {
     fn round() {
         let mut example = example();
 
-        example.round(&DroppingWLM::default());
+        example.round(&DroppingWLM);
 
         assert_eq!(example.0[0].borrow().items, [20, 23, 27, 26]);
         assert_eq!(example.0[1].borrow().items, [2080, 25, 167, 207, 401, 1046]);
@@ -318,15 +316,13 @@ mod tests {
 
     #[test]
     fn example_business() {
-        let mut example = example();
-        example.rounds(20, &DroppingWLM::default());
-        assert_eq!(example.business(), 10605);
+        assert_eq!(solve(&example(), Puzzle::P1), 10605);
     }
 
     #[test]
     fn example_business2() {
-        let mut example = example();
-        example.rounds(10000, &LCMWLM::new(&example));
-        assert_eq!(example.business(), 2713310158);
+        let example = example();
+        assert_eq!(solve(&example, Puzzle::P2), 2713310158);
+        assert_eq!(solve(&example, Puzzle::P1), 10605);
     }
 }
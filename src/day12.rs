@@ -1,7 +1,7 @@
 mod map {
-    use aoc::euclid::{Point, Vector};
+    use aoc::euclid::Point;
     use aoc::matrix::{Matrix};
-    use std::collections::{BinaryHeap, BTreeSet};
+    use std::collections::{BinaryHeap, BTreeMap, BTreeSet};
     use std::cmp::Reverse;
 
     pub struct Map {
@@ -61,31 +61,42 @@ mod map {
         }
 
         fn dijkstra<M: SearchMode>(&self, start: Point, mode: &M) -> Option<usize> {
+            self.route(start, mode).map(|route| route.len() - 1)
+        }
+
+        fn route<M: SearchMode>(&self, start: Point, mode: &M) -> Option<Vec<Point>> {
             assert!(self.map.contains(&start));
 
             let mut heap = BinaryHeap::new();
             let mut marked = BTreeSet::new();
+            let mut parents = BTreeMap::<Point, Point>::new();
 
             heap.push(Reverse(SearchItem::new(0, start, self.map[start])));
             marked.insert(start);
 
             while let Some(Reverse(item)) = heap.pop() {
                 if mode.finish(&item) {
-                    return Some(item.dist);
-                }
+                    let mut route = vec![item.point];
 
-                for vec in aoc::euclid::DirectionIterator::new().map(Vector::from) {
-                    let p = item.point.shift(&vec);
+                    while let Some(parent) = parents.get(route.last().unwrap()) {
+                        route.push(*parent);
+                    }
 
-                    if !self.map.contains(&p) || marked.contains(&p) {
+                    route.reverse();
+                    return Some(route);
+                }
+
+                for (p, &elevation) in self.map.neighbors4(item.point) {
+                    if marked.contains(&p) {
                         continue;
                     }
 
-                    let next = SearchItem::new(item.dist + 1, p, self.map[p]);
+                    let next = SearchItem::new(item.dist + 1, p, elevation);
 
                     if mode.accept(&item, &next) {
                         heap.push(Reverse(next));
                         marked.insert(p);
+                        parents.insert(p, item.point);
                     }
                 }
             }
@@ -100,97 +111,88 @@ mod map {
         pub fn scenic_path(&self) -> Option<usize> {
             self.dijkstra(self.finish, &ClimbDown(0))
         }
+
+        #[allow(dead_code)]
+        pub fn render_path(&self) -> String {
+            let mut canvas: Vec<Vec<char>> = self.map.data.iter()
+                .map(|row| row.iter().map(|h| (b'a' + *h as u8) as char).collect())
+                .collect();
+
+            if let Some(route) = self.route(self.start, &ClimbUp(self.finish)) {
+                for win in route.windows(2) {
+                    canvas[win[0].y as usize][win[0].x as usize] =
+                        match (win[1].x - win[0].x, win[1].y - win[0].y) {
+                            (1, _) => '>',
+                            (-1, _) => '<',
+                            (_, -1) => '^',
+                            _ => 'v',
+                        };
+                }
+            }
+
+            canvas[self.finish.y as usize][self.finish.x as usize] = 'E';
+
+            canvas.iter()
+                .map(|row| row.iter().collect::<String>() + "\n")
+                .collect()
+        }
     }
 
     use std::convert::TryFrom;
-    use std::fs::File;
-    use std::io::{BufReader, BufRead};
+    use std::io::BufRead;
 
-    impl TryFrom<File> for Map {
+    impl TryFrom<Box<dyn BufRead>> for Map {
         type Error = String;
 
-        fn try_from(file: std::fs::File) -> Result<Self, Self::Error> {
+        fn try_from(input: Box<dyn BufRead>) -> Result<Self, Self::Error> {
             fn char_to_val(c: char) -> isize {
                 assert!(c.is_lowercase());
                 (c as isize) - ('a' as isize)
             }
 
-            let mut line_reader = BufReader::new(file).lines();
-            let mut lines: Vec<String> = Vec::new();
-
-            while let Some(line) = aoc::io::read_line(&mut line_reader) {
-                if line.chars().any(|c| !c.is_ascii_alphabetic()) {
-                    return Err(String::from("Invalid line: ") + line.as_str());
+            fn locate(lines: &[String], mark: char) -> Result<Point, String> {
+                let mut found = lines.iter().enumerate().flat_map(|(y, line)| {
+                    line.chars().enumerate()
+                        .filter(move |(_, c)| *c == mark)
+                        .map(move |(x, _)| Point::new(x as isize, y as isize))
+                });
+
+                let point = found.next().ok_or_else(|| format!("No {} found", mark))?;
+                match found.next() {
+                    Some(p) => Err(format!("[{}, {}]: Unexpected {}", p.x, p.y, mark)),
+                    None => Ok(point),
                 }
-
-                lines.push(line);
             }
 
-            if lines.is_empty() {
-                return Err(String::from("Empty input"));
-            }
+            let lines = aoc::io::read_nonblank_block(&mut input.lines());
+            let map = Matrix::from_lines(&lines, |c| match c {
+                'S' => Ok(char_to_val('a')),
+                'E' => Ok(char_to_val('z')),
+                chr if chr.is_ascii_lowercase() => Ok(char_to_val(chr)),
+                c => Err(format!("Unexpected {}", c)),
+            })?;
 
-            if lines.iter().any(|l| l.len() != lines[0].len()) {
-                return Err(String::from("Lines of input do not have the same size"));
-            }
-
-            let mut map = Matrix::new(lines[0].len(), lines.len());
-            let mut coord = Point::new(0, 0);
-            let mut start: Option<Point> = Option::None;
-            let mut end: Option<Point> = Option::None;
-
-            for row in &lines {
-                for c in row.chars() {
-                    map[coord] = match c {
-                        'S' if start.is_none() => {
-                            start = Some(coord);
-                            char_to_val('a')
-                        }
-                        'E' if end.is_none() => {
-                            end = Some(coord);
-                            char_to_val('z')
-                        }
-                        chr if chr.is_lowercase() => {
-                            char_to_val(chr)
-                        }
-                        c => {
-                            return Err(
-                                format!("[{}, {}]: Unexpected {}", coord.x, coord.y, c));
-                        }
-                    };
-
-                    coord.x += 1;
-                }
-
-                coord = Point::new(0, coord.y + 1);
-            }
-
-            Ok(Map::new(
-                    start.ok_or_else(|| String::from("No start found"))?,
-                    end.ok_or_else(|| String::from("No end found"))?,
-                    map))
+            Ok(Map::new(locate(&lines, 'S')?, locate(&lines, 'E')?, map))
         }
     }
 }
 
-use std::fs::File;
 use aoc::args::Puzzle;
 
+fn solve(map: &map::Map, puzzle: Puzzle) -> Option<usize> {
+    match puzzle {
+        Puzzle::P1 => map.shortest_path(),
+        Puzzle::P2 => map.scenic_path(),
+    }
+}
+
 fn main() -> Result<(), String> {
     let args = aoc::args::Arguments::parse();
-    let file = File::open(args.file_name).expect("Cannot open file");
+    let map = map::Map::try_from(args.open()?)?;
 
-    let map = map::Map::try_from(file)?;
-
-    match args.puzzle {
-        Puzzle::P1 => {
-            println!("{}", map.shortest_path().unwrap());
-        }
-        Puzzle::P2 => {
-            println!("{}", map.scenic_path().unwrap());
-        }
+    for puzzle in args.puzzles() {
+        println!("{}", solve(&map, puzzle).ok_or("No path found")?);
     }
-
     Ok(())
 }
 
@@ -216,13 +218,21 @@ mod tests {
 
     #[test]
     fn example_distance() {
-        let map = example1();
-        assert_eq!(map.shortest_path(), Some(31));
+        assert_eq!(solve(&example1(), Puzzle::P1), Some(31));
+    }
+
+    #[test]
+    fn example_render() {
+        let rendered = example1().render_path();
+        let arrows = rendered.chars().filter(|c| "<>^v".contains(*c)).count();
+
+        assert_eq!(arrows, 31);
+        assert_eq!(rendered.lines().count(), 5);
+        assert!(rendered.starts_with("v"));
     }
 
     #[test]
     fn example_scenic() {
-        let map = example1();
-        assert_eq!(map.scenic_path(), Some(29));
+        assert_eq!(solve(&example1(), Puzzle::P2), Some(29));
     }
 }
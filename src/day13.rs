@@ -1,14 +1,23 @@
 use aoc::args::Puzzle;
 use std::cmp::Ordering;
-use std::fs::File;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
 use std::str::FromStr;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Packet {
     Value(u32),
     List(Vec<Packet>),
 }
 
+/* Leaf of a packet in its sort key. Empty lists sort before any value, and
+ * among themselves by the depth of the (empty) list. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum KeyLeaf {
+    Empty(usize),
+    Value(u32),
+}
+
 impl Packet {
     fn promote(&self) -> Self {
         match self {
@@ -17,6 +26,59 @@ impl Packet {
         }
     }
 
+    #[allow(dead_code)]
+    fn max_value(&self) -> Option<u32> {
+        match self {
+            Packet::Value(n) => Some(*n),
+            Packet::List(items) => items.iter().filter_map(Packet::max_value).max(),
+        }
+    }
+
+    fn _hash_into(&self, hasher: &mut DefaultHasher) {
+        match self {
+            Packet::Value(n) => {
+                hasher.write_u8(0);
+                hasher.write_u32(*n);
+            }
+            Packet::List(items) => {
+                hasher.write_u8(1);
+                hasher.write_usize(items.len());
+                items.iter().for_each(|item| item._hash_into(hasher));
+            }
+        }
+    }
+
+    /* Hash of the exact structure. Note that ‹Packet› deliberately does not
+     * implement ‹Hash›: ‹[3]› and ‹3› compare equal, but hash differently. */
+    #[allow(dead_code)]
+    fn structural_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self._hash_into(&mut hasher);
+        hasher.finish()
+    }
+
+    fn _key_into(&self, depth: usize, key: &mut Vec<(KeyLeaf, usize)>) {
+        match self {
+            Packet::Value(n) => key.push((KeyLeaf::Value(*n), depth)),
+            Packet::List(items) if items.is_empty() =>
+                key.push((KeyLeaf::Empty(depth + 1), depth)),
+            Packet::List(items) => {
+                items.iter().for_each(|item| item._key_into(depth + 1, key));
+                key.last_mut().expect("BUG: Non-empty list without leaves").1 = depth;
+            }
+        }
+    }
+
+    /* Flat key ordered the same way as the packets: every leaf along with
+     * the depth right after it, once all lists ending there are closed.
+     * Since a value compared to a list acts as if wrapped in enough lists,
+     * where the lists open does not matter, only which side closes first. */
+    fn sort_key(&self) -> Vec<(KeyLeaf, usize)> {
+        let mut key = Vec::new();
+        self._key_into(0, &mut key);
+        key
+    }
+
     fn dividers() -> [Packet; 2] {
         [
             Packet::List(vec![Packet::List(vec![Packet::Value(2)])]),
@@ -77,17 +139,17 @@ mod packet_parser {
 
     pub fn parse(input: &str) -> Result<Packet, String> {
         match list(input).finish() {
-            Ok((rest, packet)) if rest.is_empty() => Ok(packet),
+            Ok(("", packet)) => Ok(packet),
             Ok((rest, _)) => Err(String::from("Junk trailing chars: ") + rest),
             Err(err) => Err(err.to_string()),
         }
     }
 
     use std::fs::File;
-    use std::io::{BufRead, BufReader};
+    use std::io::{BufRead, BufReader, Lines};
 
     pub fn convert_to_pairs<T>(mut vec: Vec<T>) -> Vec<(T, T)> {
-        assert!(vec.len() % 2 == 0);
+        assert!(vec.len().is_multiple_of(2));
 
         let mut result = Vec::<(T, T)>::new();
         let mut drain = vec.drain(..);
@@ -99,16 +161,16 @@ mod packet_parser {
         result
     }
 
-    pub fn read_from_file(file: &File) -> Result<Vec<Packet>, String> {
+    pub fn read_from<R: BufRead>(input: R) -> Result<Vec<Packet>, String> {
         let mut packets = Vec::<Packet>::new();
 
-        let mut lineno = 0;
-        let mut lines = BufReader::new(file).lines();
+        let mut lineno: usize = 0;
+        let mut lines = input.lines();
         while let Some(line) = aoc::io::read_line(&mut lines) {
             lineno += 1;
 
             if line.is_empty() {
-                if lineno % 3 != 0 {
+                if !lineno.is_multiple_of(3) {
                     return Err(format!("{}: Expected empty line, got {}", lineno, line));
                 }
 
@@ -120,6 +182,48 @@ mod packet_parser {
 
         Ok(packets)
     }
+
+    fn read_all_lines<T>(lines: &mut Lines<T>) -> Result<Vec<Packet>, Vec<(usize, String)>>
+            where T: BufRead {
+        let mut packets = Vec::<Packet>::new();
+        let mut errors = Vec::<(usize, String)>::new();
+
+        let mut lineno: usize = 0;
+        while let Some(line) = aoc::io::read_line(lines) {
+            lineno += 1;
+
+            if line.is_empty() {
+                if !lineno.is_multiple_of(3) {
+                    errors.push((lineno, String::from("Unexpected empty line")));
+                }
+
+                continue;
+            }
+
+            match line.parse::<Packet>() {
+                Ok(packet) => packets.push(packet),
+                Err(err) => errors.push((lineno, err)),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(packets)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /* Unlike ‹read_from›, this does not stop at the first error,
+     * but reports every malformed line along with its number. */
+    #[allow(dead_code)]
+    pub fn read_all(file: &File) -> Result<Vec<Packet>, Vec<(usize, String)>> {
+        read_all_lines(&mut BufReader::new(file).lines())
+    }
+
+    #[cfg(test)]
+    pub fn read_all_str(input: &str) -> Result<Vec<Packet>, Vec<(usize, String)>> {
+        read_all_lines(&mut input.as_bytes().lines())
+    }
 }
 
 impl FromStr for Packet {
@@ -130,21 +234,30 @@ impl FromStr for Packet {
     }
 }
 
-fn filter_correct(
-    pairs: &'_ [(Packet, Packet)]
-) -> impl Iterator<Item = (usize, &(Packet, Packet))> + '_ {
-    pairs.iter().enumerate()
-        .filter(|(_, (p1, p2))| p1 < p2)
-        .map(|(i, v)| (i + 1, v))
+/* Splits (1-based) pair indices into correct, equal and reversed pairs. */
+fn classify(pairs: &[(Packet, Packet)]) -> (Vec<usize>, Vec<usize>, Vec<usize>) {
+    let mut correct = Vec::<usize>::new();
+    let mut equal = Vec::<usize>::new();
+    let mut reversed = Vec::<usize>::new();
+
+    for (i, (p1, p2)) in pairs.iter().enumerate() {
+        match p1.cmp(p2) {
+            Ordering::Less => correct.push(i + 1),
+            Ordering::Equal => equal.push(i + 1),
+            Ordering::Greater => reversed.push(i + 1),
+        }
+    }
+
+    (correct, equal, reversed)
 }
 
-fn correct_indices(pairs: &'_ [(Packet, Packet)]) -> impl Iterator<Item = usize> + '_ {
-    filter_correct(pairs).map(|(i, _)| i)
+fn correct_indices(pairs: &[(Packet, Packet)]) -> impl Iterator<Item = usize> {
+    classify(pairs).0.into_iter()
 }
 
 fn decoder_key(mut packets: Vec<Packet>) -> usize {
     packets.extend(Packet::dividers());
-    packets.sort();
+    packets.sort_by_cached_key(Packet::sort_key);
 
     let div2 = packets.binary_search(&Packet::dividers()[0])
         .expect("Divider [[2]] not found");
@@ -154,19 +267,23 @@ fn decoder_key(mut packets: Vec<Packet>) -> usize {
     (div2 + 1) * (div6 + 1)
 }
 
-fn main() -> Result<(), String> {
-    let args = aoc::args::Arguments::parse();
-    let file = File::open(args.file_name).expect("Cannot open file");
-
-    let packets = packet_parser::read_from_file(&file)?;
-
-    println!("{}", match args.puzzle {
+fn solve(packets: &[Packet], puzzle: Puzzle) -> usize {
+    match puzzle {
         Puzzle::P1 => {
-            let pairs = packet_parser::convert_to_pairs(packets);
+            let pairs = packet_parser::convert_to_pairs(packets.to_vec());
             correct_indices(&pairs).sum::<usize>()
         }
-        Puzzle::P2 => decoder_key(packets),
-    });
+        Puzzle::P2 => decoder_key(packets.to_vec()),
+    }
+}
+
+fn main() -> Result<(), String> {
+    let args = aoc::args::Arguments::parse();
+    let packets = packet_parser::read_from(args.open()?)?;
+
+    for puzzle in args.puzzles() {
+        println!("{}", solve(&packets, puzzle));
+    }
 
     Ok(())
 }
@@ -225,15 +342,94 @@ mod tests {
 
     #[test]
     fn example1() {
-        let examples = packet_parser::convert_to_pairs(examples());
-        dbg!(&examples);
-        let sum = correct_indices(&examples).sum::<usize>();
-
-        assert_eq!(sum, 13);
+        assert_eq!(solve(&examples(), Puzzle::P1), 13);
     }
 
     #[test]
     fn example2() {
-        assert_eq!(decoder_key(examples()), 140);
+        assert_eq!(solve(&examples(), Puzzle::P2), 140);
+    }
+
+    #[test]
+    fn keyed_sort_order() {
+        let mut direct = examples();
+        direct.extend(Packet::dividers());
+        direct.sort();
+
+        let mut keyed = examples();
+        keyed.extend(Packet::dividers());
+        keyed.sort_by_cached_key(Packet::sort_key);
+
+        assert_eq!(keyed, direct);
+    }
+
+    #[test]
+    fn sort_key_matches_cmp() {
+        let tricky = [
+            "[]", "[[]]", "[[[]]]", "[[],[]]", "[0]", "[[0]]", "[[[0]]]",
+            "[0,0]", "[[0,0]]", "[[0],0]", "[0,[]]", "[[],0]", "[[0],[]]",
+            "[[[]],0]", "[1,[]]", "[[1],[]]", "[[1,[]],2]", "[10]", "[[10]]",
+        ];
+
+        let mut packets = examples();
+        packets.extend(Packet::dividers());
+        packets.extend(tricky.iter().map(|text| text.parse::<Packet>().unwrap()));
+
+        for a in &packets {
+            for b in &packets {
+                assert_eq!(a.sort_key().cmp(&b.sort_key()), a.cmp(b), "{:?} {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn max_and_hash() {
+        let text = "[1,[2,[3,[4,[5,6,7]]]],8,9]";
+        let (a, b) = (text.parse::<Packet>().unwrap(), text.parse::<Packet>().unwrap());
+
+        assert_eq!(a.max_value(), Some(9));
+        assert_eq!(a.max_value(), b.max_value());
+        assert_eq!(a.structural_hash(), b.structural_hash());
+
+        let c = "[1,[2,[3,[4,[5,6,0]]]],8,9]".parse::<Packet>().unwrap();
+        assert_ne!(a.structural_hash(), c.structural_hash());
+        assert_ne!(
+            "[[]]".parse::<Packet>().unwrap().structural_hash(),
+            "[[],[]]".parse::<Packet>().unwrap().structural_hash()
+        );
+        assert_eq!("[[],[[]]]".parse::<Packet>().unwrap().max_value(), None);
+    }
+
+    #[test]
+    fn classify_equal() {
+        use Packet::Value as V;
+        use Packet::List as L;
+
+        let pairs = vec![
+            (L(vec![V(1)]), L(vec![V(2)])),
+            (L(vec![V(3)]), V(3)),
+            (L(vec![V(5)]), L(vec![V(4)])),
+        ];
+
+        let (correct, equal, reversed) = classify(&pairs);
+        assert_eq!(correct, vec![1]);
+        assert_eq!(equal, vec![2]);
+        assert_eq!(reversed, vec![3]);
+    }
+
+    #[test]
+    fn read_all_errors() {
+        let input = "[1,1,3]\n[1,[2]\n\n[[1],4]\n[x]\n";
+        let errors = packet_parser::read_all_str(input).expect_err("Input is malformed");
+
+        assert_eq!(errors.iter().map(|(n, _)| *n).collect::<Vec<_>>(), vec![2, 5]);
+    }
+
+    #[test]
+    fn read_all_valid() {
+        let packets = packet_parser::read_all_str("[1]\n[[2]]\n\n[]\n[3]\n")
+            .expect("Input is valid");
+
+        assert_eq!(packets.len(), 4);
     }
 }
@@ -2,24 +2,37 @@ mod path_segment {
     use aoc::euclid::Point;
     use std::str::FromStr;
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone)]
     pub struct PathSegment(pub Vec<Point>);
 
-    impl FromStr for PathSegment {
-        type Err = String;
+    impl PathSegment {
+        /* Drops repeated points and inner points of straight runs. */
+        pub fn optimize(&mut self) {
+            let mut points = Vec::<Point>::with_capacity(self.0.len());
 
-        fn from_str(input: &str) -> Result<Self, Self::Err> {
-            parser::path_segment(input)
+            for p in self.0.drain(..) {
+                if points.last() == Some(&p) {
+                    continue;
+                }
+
+                if let [.., a, b] = points[..] {
+                    if a.direction(&b) == b.direction(&p) {
+                        points.pop();
+                    }
+                }
+
+                points.push(p);
+            }
+
+            self.0 = points;
         }
     }
 
-    impl PathSegment {
-        pub fn size(&self) -> Point {
-            use std::cmp::max;
+    impl FromStr for PathSegment {
+        type Err = String;
 
-            self.0.iter().fold(Point::new(0, 0),
-                |b, p| Point::new(max(b.x, p.x), max(b.y, p.y))
-            )
+        fn from_str(input: &str) -> Result<Self, Self::Err> {
+            parser::path_segment(input)
         }
     }
 
@@ -27,24 +40,14 @@ mod path_segment {
         use super::{PathSegment, Point};
 
         use nom::{
-            bytes::complete::tag,
-            character::complete::{char, digit1},
+            bytes::complete::{is_not, tag},
             combinator::map_res,
             multi::separated_list1,
-            sequence::separated_pair,
             Finish, IResult,
         };
 
-        fn coordinate(input: &str) -> IResult<&str, isize> {
-            map_res(digit1, |n: &str| n.parse::<isize>())(input)
-        }
-
         fn point(input: &str) -> IResult<&str, Point> {
-            let (input, (x, y)) = separated_pair(
-                coordinate, char(','), coordinate
-            )(input)?;
-
-            Ok((input, Point::new(x, y)))
+            map_res(is_not(" "), |p: &str| p.parse::<Point>())(input)
         }
 
         fn path(input: &str) -> IResult<&str, Vec<Point>> {
@@ -53,7 +56,7 @@ mod path_segment {
 
         pub fn path_segment(input: &str) -> Result<PathSegment, String> {
             match path(input).finish() {
-                Ok((rest, list)) if rest.is_empty() => Ok(PathSegment(list)),
+                Ok(("", list)) => Ok(PathSegment(list)),
                 Ok((rest, _)) => Err(String::from("Junk trailing chars: ") + rest),
                 Err(err) => Err(err.to_string()),
             }
@@ -63,16 +66,21 @@ mod path_segment {
 
 mod scan {
     use super::path_segment::PathSegment;
-    use aoc::euclid::Point;
+    use aoc::euclid::{BoundingBox, Point};
     use std::fs::File;
     use std::io::{BufRead, BufReader};
 
+    #[derive(Clone)]
     pub struct Scan(pub Vec<PathSegment>);
 
     impl Scan {
         pub fn new_from_file(file: &File) -> Result<Scan, String> {
+            Self::new_from_reader(BufReader::new(file))
+        }
+
+        pub fn new_from_reader<R: BufRead>(reader: R) -> Result<Scan, String> {
             let mut paths = Vec::<PathSegment>::new();
-            let mut lines = BufReader::new(file).lines();
+            let mut lines = reader.lines();
 
             while let Some(line) = aoc::io::read_line(&mut lines) {
                 paths.push(line.parse::<PathSegment>()?);
@@ -81,17 +89,23 @@ mod scan {
             Ok(Scan(paths))
         }
 
-        pub fn size(&self) -> Point {
-            use std::cmp::max;
+        pub fn points(&self) -> impl Iterator<Item = Point> + '_ {
+            self.0.iter().flat_map(|segment| segment.0.iter().copied())
+        }
+
+        pub fn bounds(&self) -> Option<BoundingBox> {
+            BoundingBox::from_points(self.points())
+        }
 
-            self.0.iter().map(PathSegment::size).fold(
-                Point::default(),
-                |b, p| Point::new(max(b.x, p.x), max(b.y, p.y))
-            )
+        #[allow(dead_code)]
+        pub fn optimize(&mut self) {
+            for segment in &mut self.0 {
+                segment.optimize();
+            }
         }
 
         pub fn add_floor(&mut self, source: &Point) {
-            let floor_y = self.size().y + 2;
+            let floor_y = self.bounds().map_or(0, |b| b.max.y) + 2;
             self.0.push(PathSegment(vec![
                 Point::new(source.x - (floor_y + 10), floor_y),
                 Point::new(source.x + (floor_y + 10), floor_y),
@@ -102,77 +116,85 @@ mod scan {
 
 mod map {
     use super::{path_segment::PathSegment, scan::Scan};
-    use aoc::euclid::{Point, Vector};
+    use aoc::euclid::{BoundingBox, Point, Vector};
     use aoc::matrix::Matrix;
     use std::fs::File;
 
-    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    #[derive(Clone, Default, PartialEq, Eq)]
     pub enum Tile {
+        #[default]
         Empty,
         Sand,
         Rock,
     }
 
-    impl Default for Tile {
-        fn default() -> Self {
-            Self::Empty
-        }
+    /* Tiles are stored relative to ‹offset›, the top-left corner of the
+     * bounding box of the scan and the sand source. */
+    #[derive(Clone)]
+    pub struct Map {
+        tiles: Matrix<Tile>,
+        offset: Point,
     }
 
-    pub struct Map(Matrix<Tile>);
-
     impl Map {
-        fn fill_segment(map: &mut Matrix<Tile>, segment: &PathSegment) {
+        fn fill_segment(&mut self, segment: &PathSegment) {
             for win in segment.0.windows(2) {
                 assert!(win[0].x == win[1].x || win[0].y == win[1].y);
-                let dir = win[0].direction(&win[1]);
 
-                let mut cursor = win[0];
-                while cursor != win[1] {
-                    map[cursor] = Tile::Rock;
-                    cursor = cursor.shift(&dir);
+                for p in win[0].line_to(&win[1]) {
+                    self.set(p, Tile::Rock);
                 }
-
-                map[cursor] = Tile::Rock;
             }
         }
 
-        pub fn new_from_scan(scan: &Scan) -> Self {
-            let size = scan.size();
-            let mut map = Matrix::new(size.x as usize + 1, size.y as usize + 1);
+        pub fn new_from_scan(scan: &Scan, source: &Point) -> Self {
+            let bounds = BoundingBox::from_points(scan.points().chain([*source]))
+                .expect("BUG: Bounding box of at least one point is empty");
+
+            let mut map = Self {
+                tiles: Matrix::new(bounds.width(), bounds.height()),
+                offset: bounds.min,
+            };
 
             for segment in &scan.0 {
-                Self::fill_segment(&mut map, segment);
+                map.fill_segment(segment);
             }
 
-            Self(map)
+            map
+        }
+
+        #[allow(dead_code)]
+        pub fn new_from_file(file: &File, source: &Point) -> Result<Map, String> {
+            Ok(Self::new_from_scan(&super::scan::Scan::new_from_file(file)?, source))
+        }
+
+        fn get(&self, p: Point) -> Option<&Tile> {
+            self.tiles.get(p - self.offset)
+        }
+
+        fn set(&mut self, p: Point, tile: Tile) {
+            self.tiles[p - self.offset] = tile;
         }
 
         #[allow(dead_code)]
-        pub fn new_from_file(file: &File) -> Result<Map, String> {
-            Ok(Self::new_from_scan(&super::scan::Scan::new_from_file(file)?))
+        pub fn render(&self) -> String {
+            self.tiles.render(|cell| match cell {
+                Tile::Empty => '.',
+                Tile::Rock => '#',
+                Tile::Sand => '%',
+            }) + "\n"
         }
 
         #[allow(dead_code)]
         pub fn draw(&self) {
             println!("Map:");
-            for row in &self.0.data {
-                for cell in row {
-                    print!("{}", match cell {
-                        Tile::Empty => '.',
-                        Tile::Rock => '#',
-                        Tile::Sand => '%',
-                    })
-                }
-
-                println!();
-            }
+            print!("{}", self.render());
         }
 
         pub fn drop_sand(&mut self, from: &Point) -> Result<Point, Point> {
             let mut current = *from;
 
-            if self.0[current] != Tile::Empty {
+            if self.get(current) != Some(&Tile::Empty) {
                 return Err(current);
             }
 
@@ -184,23 +206,40 @@ mod map {
                 ];
 
                 for next in locations {
-                    if !self.0.contains(&next) {
-                        return Err(next);
-                    }
-
-                    if self.0[next] == Tile::Empty {
-                        current = next;
-                        continue 'loc;
+                    match self.get(next) {
+                        None => return Err(next),
+                        Some(Tile::Empty) => {
+                            current = next;
+                            continue 'loc;
+                        }
+                        Some(_) => {}
                     }
                 }
 
-                self.0[current] = Tile::Sand;
+                self.set(current, Tile::Sand);
                 break;
             }
 
             Ok(current)
         }
 
+        /* Checks on a copy of the map whether some grain falls off its sides
+         * before the source gets blocked. Every grain that does not leak
+         * settles on a distinct tile, so the simulation is bounded. */
+        #[allow(dead_code)]
+        pub fn leaks(&self, from: &Point) -> bool {
+            let mut map = self.clone();
+
+            for _ in 0 ..= self.tiles.width * self.tiles.height {
+                match map.drop_sand(from) {
+                    Ok(_) => continue,
+                    Err(p) => return p != *from,
+                }
+            }
+
+            false
+        }
+
         pub fn fill(&mut self, from: &Point) -> usize {
             std::iter::repeat_with(|| self.drop_sand(from))
                 .take_while(|r| r.is_ok())
@@ -213,22 +252,26 @@ use aoc::args::Puzzle;
 use aoc::euclid::Point;
 use map::Map;
 use scan::Scan;
-use std::fs::File;
+
+/* Units of sand that come to rest, on a copy of the scan with a floor
+ * added for the second puzzle. */
+fn solve(scan: &Scan, source: &Point, puzzle: Puzzle) -> usize {
+    let mut scan = scan.clone();
+    if puzzle == Puzzle::P2 {
+        scan.add_floor(source);
+    }
+
+    Map::new_from_scan(&scan, source).fill(source)
+}
 
 fn main() -> Result<(), String> {
     let args = aoc::args::Arguments::parse();
-    let file = File::open(args.file_name).expect("Cannot open file");
 
-    let source = Point::new(500, 0);
-    let mut scan = Scan::new_from_file(&file)?;
-
-    if args.puzzle == Puzzle::P2 {
-        scan.add_floor(&source);
+    let scan = Scan::new_from_reader(args.open()?)?;
+    for puzzle in args.puzzles() {
+        println!("{}", solve(&scan, &Point::new(500, 0), puzzle));
     }
 
-    let mut map = Map::new_from_scan(&scan);
-    println!("{}", map.fill(&source));
-
     Ok(())
 }
 
@@ -253,19 +296,80 @@ mod tests {
         ])
     }
 
+    #[test]
+    fn parse_segment() {
+        let segment = "498,4 -> 498,6 -> 496,6".parse::<path_segment::PathSegment>()
+            .expect("Cannot parse segment");
+
+        assert_eq!(segment.0, vec![
+            Point::new(498, 4),
+            Point::new(498, 6),
+            Point::new(496, 6),
+        ]);
+
+        assert!("498,4 -> x".parse::<path_segment::PathSegment>().is_err());
+        assert!("498,4 ->".parse::<path_segment::PathSegment>().is_err());
+    }
+
     #[test]
     fn example1() {
-        let mut map = map::Map::new_from_scan(&example_scan());
-        assert_eq!(map.fill(&Point::new(500, 0)), 24);
+        assert_eq!(solve(&example_scan(), &Point::new(500, 0), Puzzle::P1), 24);
     }
 
     #[test]
-    fn example2() {
+    fn optimize() {
+        let source = Point::new(1, 0);
+        let mut scan = scan::Scan(vec![
+            path_segment::PathSegment(vec![
+                Point::new(0, 0),
+                Point::new(0, 1),
+                Point::new(0, 2),
+            ]),
+            path_segment::PathSegment(vec![
+                Point::new(3, 2),
+                Point::new(3, 2),
+                Point::new(5, 2),
+                Point::new(6, 2),
+                Point::new(6, 4),
+            ]),
+        ]);
+
+        let before = map::Map::new_from_scan(&scan, &source).render();
+        scan.optimize();
+
+        assert_eq!(scan.0[0].0, vec![Point::new(0, 0), Point::new(0, 2)]);
+        assert_eq!(scan.0[1].0, vec![Point::new(3, 2), Point::new(6, 2), Point::new(6, 4)]);
+        assert_eq!(map::Map::new_from_scan(&scan, &source).render(), before);
+    }
+
+    #[test]
+    fn negative_coordinates() {
+        let source = Point::new(0, -10);
+        let scan = scan::Scan(vec![
+            path_segment::PathSegment(vec![Point::new(-2, -5), Point::new(2, -5)]),
+        ]);
+
+        let mut map = map::Map::new_from_scan(&scan, &source);
+        assert_eq!(map.fill(&source), 4);
+    }
+
+    #[test]
+    fn narrow_floor_leaks() {
         let source = Point::new(500, 0);
+        let scan = scan::Scan(vec![
+            path_segment::PathSegment(vec![Point::new(499, 3), Point::new(501, 3)]),
+        ]);
+
+        let map = map::Map::new_from_scan(&scan, &source);
+        assert!(map.leaks(&source));
+
         let mut scan = example_scan();
         scan.add_floor(&source);
+        assert!(!map::Map::new_from_scan(&scan, &source).leaks(&source));
+    }
 
-        let mut map = map::Map::new_from_scan(&scan);
-        assert_eq!(map.fill(&source), 93);
+    #[test]
+    fn example2() {
+        assert_eq!(solve(&example_scan(), &Point::new(500, 0), Puzzle::P2), 93);
     }
 }
@@ -1,7 +1,7 @@
 mod sensor {
     use aoc::euclid::Point;
     use std::collections::BTreeSet;
-    use std::fs::File;
+    use std::io::BufRead;
     use std::str::FromStr;
 
     #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
@@ -99,7 +99,7 @@ mod sensor {
         }
     }
 
-    #[derive(PartialEq, Eq, Copy, Clone)]
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
     pub struct Sensor {
         pub position: Point,
         pub beacon: Point,
@@ -108,8 +108,7 @@ mod sensor {
 
     impl Sensor {
         pub fn new(position: Point, beacon: Point) -> Self {
-            let range = (position.x - beacon.x).abs() + (position.y - beacon.y).abs();
-            Self { position, beacon, range: range as usize }
+            Self { position, beacon, range: position.manhattan_distance(&beacon) }
         }
 
         pub fn cut<AP>(&self, pos: isize) -> Option<Range>
@@ -127,6 +126,14 @@ mod sensor {
             }
         }
 
+        #[allow(dead_code)]
+        pub fn row_ranges(&self) -> impl Iterator<Item = (isize, Range)> + '_ {
+            let range = self.range as isize;
+
+            (self.position.y - range ..= self.position.y + range)
+                .filter_map(|y| self.cut::<XAxis>(y).map(|r| (y, r)))
+        }
+
         #[allow(dead_code)]
         pub fn range<AP>(&self) -> Range
                 where AP: AxisProjection {
@@ -135,29 +142,47 @@ mod sensor {
 
         #[allow(dead_code)]
         pub fn distance(&self, point: &Point) -> usize {
-            ((self.position.x - point.x).abs() + (self.position.y - point.y).abs()) as usize
+            self.position.manhattan_distance(point)
         }
 
         #[allow(dead_code)]
         pub fn contains(&self, point: &Point) -> bool {
             self.distance(point) <= self.range
         }
+
+        /* Corners of the covered area, in the order top, right, bottom, left,
+         * with ‹y› growing downwards as in the puzzle (and SVG). */
+        #[allow(dead_code)]
+        pub fn diamond_vertices(&self) -> [Point; 4] {
+            let (x, y, r) = (self.position.x, self.position.y, self.range as isize);
+
+            [Point::new(x, y - r), Point::new(x + r, y), Point::new(x, y + r), Point::new(x - r, y)]
+        }
     }
 
     #[derive(Default)]
     pub struct Scan(pub Vec<Sensor>);
 
     impl Scan {
-        pub fn new_from_file(file: File) -> Self {
+        /* Besides the scan, returns (1-based) numbers and contents of
+         * non-empty lines that are not valid sensor descriptions. */
+        pub fn new_from_reader<R: BufRead>(reader: R) -> (Self, Vec<(usize, String)>) {
             let mut sensors = Vec::<Sensor>::new();
+            let mut skipped = Vec::<(usize, String)>::new();
+            let mut lines = reader.lines();
+            let mut lineno: usize = 0;
+
+            while let Some(line) = aoc::io::read_line(&mut lines) {
+                lineno += 1;
 
-            for line in aoc::io::lines(file) {
-                if let Ok(sensor) = line.parse() {
-                    sensors.push(sensor);
+                match line.parse() {
+                    Ok(sensor) => sensors.push(sensor),
+                    Err(_) if line.trim().is_empty() => {}
+                    Err(_) => skipped.push((lineno, line)),
                 }
             }
 
-            Self(sensors)
+            (Self(sensors), skipped)
         }
 
         pub fn cut<AP>(&self, position: isize) -> sparse_range::SparseRange
@@ -173,6 +198,14 @@ mod sensor {
             builder.build()
         }
 
+        #[allow(dead_code)]
+        pub fn covered_inclusive<AP>(&self, position: isize)
+                -> impl Iterator<Item = core::ops::RangeInclusive<isize>> + '_
+                where AP: AxisProjection {
+            self.cut::<AP>(position).0.into_iter()
+                .map(|range| core::ops::RangeInclusive::from(&range))
+        }
+
         pub fn beacons<AP>(&self, position: isize) -> usize
                 where AP: AxisProjection {
             let beacons = self.0.iter().filter(|sensor| AP::transposed(&sensor.beacon) == position)
@@ -187,11 +220,40 @@ mod sensor {
             self.cut::<AP>(position).len() - self.beacons::<AP>(position)
         }
 
-        fn find_beacons(&self, xr: &Range, yr: &Range) -> Vec<Point> {
+        #[allow(dead_code)]
+        pub fn tiles_without_beacons_over<AP>(&self, rows: Range) -> usize
+                where AP: AxisProjection {
+            let covered = core::ops::RangeInclusive::from(&rows)
+                .map(|position| self.cut::<AP>(position).len())
+                .sum::<usize>();
+
+            let beacons = self.0.iter()
+                .map(|sensor| sensor.beacon)
+                .filter(|beacon| {
+                    let position = AP::transposed(beacon);
+                    rows.min <= position && position <= rows.max
+                })
+                .collect::<BTreeSet<Point>>();
+
+            covered - beacons.len()
+        }
+
+        fn find_beacons(&self, xr: &Range, yr: &Range, budget: Option<usize>)
+                -> Result<Vec<Point>, String> {
             let mut builder = SparseRangeBuilder::new();
+            let mut steps: usize = 0;
+
+            let mut step = || {
+                steps += 1;
+                match budget {
+                    Some(limit) if steps > limit => Err(String::from("budget exceeded")),
+                    _ => Ok(()),
+                }
+            };
 
             println!("Searching for candidate x axis");
             for y in core::ops::RangeInclusive::from(yr) {
+                step()?;
                 if y % 100_000 == 0 {
                     println!("  {:3} %", (100 * (y - yr.min)) / yr.len() as isize);
                 }
@@ -209,6 +271,7 @@ mod sensor {
 
             for candidate_x_range in ranges.0 {
                 for x in core::ops::RangeInclusive::from(&candidate_x_range) {
+                    step()?;
                     for range in self.cut::<YAxis>(x).holes_in_range(yr).0 {
                         for y in core::ops::RangeInclusive::from(&range) {
                             println!("  Found point [{}, {}]", x, y);
@@ -218,11 +281,13 @@ mod sensor {
                 }
             }
 
-            points
+            Ok(points)
         }
 
-        pub fn tuning_frequency(&self, area: &Range) -> Result<isize, String> {
-            let beacons = self.find_beacons(area, area);
+        /* The optional ‹budget› limits the number of scanned rows and columns. */
+        pub fn tuning_frequency(&self, area: &Range, budget: Option<usize>)
+                -> Result<isize, String> {
+            let beacons = self.find_beacons(area, area, budget)?;
             let count = beacons.len();
 
             if count == 0 {
@@ -321,10 +386,15 @@ mod sensor {
         lazy_static! {
             static ref SENSOR_RE: Regex =
                 Regex::new(r"Sensor at x=(-?\d+), y=(-?\d+): closest beacon is at x=(-?\d+), y=(-?\d+)").unwrap();
+            static ref SENSOR_PLAIN_RE: Regex =
+                Regex::new(r"^\s*Sensor: \((-?\d+), ?(-?\d+)\) Beacon: \((-?\d+), ?(-?\d+)\)\s*$").unwrap();
         }
 
+        /* Accepts both the canonical puzzle format and the shorter
+         * ‹Sensor: (x,y) Beacon: (x,y)›. */
         pub fn parse_sensor(line: &str) -> Option<Sensor> {
-            let cap = SENSOR_RE.captures(line)?;
+            let cap = SENSOR_RE.captures(line)
+                .or_else(|| SENSOR_PLAIN_RE.captures(line))?;
 
             let sensor_x = cap.get(1)?.as_str().parse::<isize>().unwrap();
             let sensor_y = cap.get(2)?.as_str().parse::<isize>().unwrap();
@@ -345,18 +415,31 @@ mod sensor {
 }
 
 use aoc::args::Puzzle;
-use std::fs::File;
+
+/* Side of the area the distress beacon is in; the first puzzle asks about
+ * the row in the middle of it. */
+const AREA_SIZE: isize = 4_000_000;
+
+fn solve(scan: &sensor::Scan, size: isize, puzzle: Puzzle) -> Result<isize, String> {
+    match puzzle {
+        Puzzle::P1 => Ok(scan.tiles_without_beacons::<sensor::XAxis>(size / 2) as isize),
+        Puzzle::P2 => aoc::args::timed("tuning_frequency", || {
+            scan.tuning_frequency(&(0, size).into(), None)
+        }),
+    }
+}
 
 fn main() -> Result<(), String> {
     let args = aoc::args::Arguments::parse();
-    let file = File::open(args.file_name).expect("Cannot open file");
+    let (scan, skipped) = sensor::Scan::new_from_reader(args.open()?);
 
-    let scan = sensor::Scan::new_from_file(file);
+    for (lineno, line) in &skipped {
+        eprintln!("Skipped line {}: {}", lineno, line);
+    }
 
-    println!("{}", match args.puzzle {
-        Puzzle::P1 => scan.tiles_without_beacons::<sensor::XAxis>(2_000_000) as isize,
-        Puzzle::P2 => scan.tuning_frequency(&(0, 4_000_000).into())?,
-    });
+    for puzzle in args.puzzles() {
+        println!("{}", solve(&scan, AREA_SIZE, puzzle)?);
+    }
 
     Ok(())
 }
@@ -364,6 +447,22 @@ fn main() -> Result<(), String> {
 #[cfg(test)]
 mod tests {
     use super::sensor::*;
+    use super::{solve, Puzzle};
+    use aoc::euclid::Point;
+
+    #[test]
+    fn sensor_diamond() {
+        let sensor = Sensor::new((5, 5).into(), (7, 6).into());
+
+        assert_eq!(sensor.range, 3);
+        assert_eq!(sensor.diamond_vertices(), [
+            Point::new(5, 2),
+            Point::new(8, 5),
+            Point::new(5, 8),
+            Point::new(2, 5),
+        ]);
+        assert!(sensor.diamond_vertices().iter().all(|p| sensor.distance(p) == sensor.range));
+    }
 
     #[test]
     fn sensor_cut() {
@@ -382,6 +481,36 @@ mod tests {
         assert_eq!(sensor.cut::<XAxis>(-3), None);
     }
 
+    #[test]
+    fn sensor_row_ranges() {
+        let sensor = Sensor::new((2, 2).into(), (3, 3).into());
+        let rows = sensor.row_ranges().collect::<Vec<_>>();
+
+        assert_eq!(rows, vec![
+            (0, (2, 2).into()),
+            (1, (1, 3).into()),
+            (2, (0, 4).into()),
+            (3, (1, 3).into()),
+            (4, (2, 2).into()),
+        ]);
+
+        for (y, range) in rows {
+            assert_eq!(sensor.cut::<XAxis>(y), Some(range));
+        }
+    }
+
+    #[test]
+    fn sensor_formats() {
+        let canonical = "Sensor at x=2, y=18: closest beacon is at x=-2, y=15"
+            .parse::<Sensor>().expect("Cannot parse canonical format");
+        let plain = "Sensor: (2,18) Beacon: (-2, 15)"
+            .parse::<Sensor>().expect("Cannot parse plain format");
+
+        assert_eq!(canonical, plain);
+        assert_eq!(plain, Sensor::new((2, 18).into(), (-2, 15).into()));
+        assert!("Sensor: (2,18)".parse::<Sensor>().is_err());
+    }
+
     fn example_scan() -> Scan {
         Scan(vec![
             Sensor::new((2, 18).into(), (-2, 15).into()),
@@ -403,13 +532,60 @@ mod tests {
 
     #[test]
     fn example1() {
+        assert_eq!(solve(&example_scan(), 20, Puzzle::P1), Ok(26));
+    }
+
+    #[test]
+    fn example1_covered_inclusive() {
         let scan = example_scan();
-        assert_eq!(scan.tiles_without_beacons::<XAxis>(10), 26);
+        let covered = scan.covered_inclusive::<XAxis>(10).flatten().count();
+
+        /* The beacon at (2, 10) is covered but does not count as beacon-free. */
+        assert_eq!(covered, 27);
+        assert_eq!(scan.covered_inclusive::<XAxis>(10).collect::<Vec<_>>(), vec![-2 ..= 24]);
+    }
+
+    #[test]
+    fn example1_over_rows() {
+        let scan = example_scan();
+        let beacons = scan.0.iter().map(|s| s.beacon).collect::<Vec<_>>();
+
+        let manual = (9 ..= 11).flat_map(|y| (-20 ..= 40).map(move |x| Point::new(x, y)))
+            .filter(|p| scan.0.iter().any(|s| s.contains(p)) && !beacons.contains(p))
+            .count();
+
+        assert_eq!(scan.tiles_without_beacons_over::<XAxis>((9, 11).into()), manual);
+        assert_eq!(
+            scan.tiles_without_beacons_over::<XAxis>((10, 10).into()),
+            scan.tiles_without_beacons::<XAxis>(10)
+        );
     }
 
     #[test]
     fn example2() {
+        assert_eq!(solve(&example_scan(), 20, Puzzle::P2), Ok(56000011));
+    }
+
+    #[test]
+    fn tuning_budget() {
         let scan = example_scan();
-        assert_eq!(scan.tuning_frequency(&(0, 20).into()), Ok(56000011));
+        assert_eq!(scan.tuning_frequency(&(0, 4_000_000).into(), Some(10)),
+                   Err(String::from("budget exceeded")));
+        assert_eq!(scan.tuning_frequency(&(0, 20).into(), Some(1_000)), Ok(56000011));
+    }
+
+    #[test]
+    fn skipped_lines() {
+        let input = "Sensor at x=2, y=18: closest beacon is at x=-2, y=15\n\
+                     Sensor at x=9, y=16 closest beacon is at x=10, y=16\n\
+                     \n\
+                     Sensor: (13,2) Beacon: (15,3)\n";
+
+        let (scan, skipped) = Scan::new_from_reader(input.as_bytes());
+
+        assert_eq!(scan.0.len(), 2);
+        assert_eq!(skipped, vec![
+            (2, String::from("Sensor at x=9, y=16 closest beacon is at x=10, y=16")),
+        ]);
     }
 }
@@ -3,3 +3,4 @@ pub use crate::aoc::args;
 pub use crate::aoc::euclid;
 pub use crate::aoc::io;
 pub use crate::aoc::matrix;
+pub use crate::aoc::profile;